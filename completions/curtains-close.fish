@@ -9,5 +9,31 @@ complete -c curtains-close -s n -l buttons-per-row -r
 complete -c curtains-close -s x -l column-spacing -r
 complete -c curtains-close -s y -l row-spacing -r
 complete -c curtains-close -s d -l delay-before-closing -r
+complete -c curtains-close -s a -l default-action -r
+complete -c curtains-close -s t -l timeout-seconds -r
+complete -c curtains-close -s f -l initial-focus -r
+complete -c curtains-close -s m -l monitor-mode -r
+complete -c curtains-close -s o -l output -r
+complete -c curtains-close -s B -l dismiss-on-backdrop-click -r -f -a "true\t''
+false\t''"
+complete -c curtains-close -s K -l dismiss-keys -r
+complete -c curtains-close -s Y -l layer -r -f -a "top\t''
+overlay\t''"
+complete -c curtains-close -s A -l anchor -r -f -a "top\t''
+right\t''
+bottom\t''
+left\t''"
+complete -c curtains-close -s W -l width -r
+complete -c curtains-close -s H -l height -r
+complete -c curtains-close -s I -l icon-size -r
+complete -c curtains-close -s p -l print -l dry-run -r -f -a "id\t''
+command\t''"
 complete -c curtains-close -s v -l version
+complete -c curtains-close -s k -l show-keybinds
+complete -c curtains-close -s N -l no-dismiss
+complete -c curtains-close -s T -l no-layer-shell
+complete -c curtains-close -l toggle
+complete -c curtains-close -l open
+complete -c curtains-close -l close
+complete -c curtains-close -l check-config
 complete -c curtains-close -s h -l help -d 'Print help'
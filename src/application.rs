@@ -1,23 +1,33 @@
 use gdk4::Key;
-use glib::Propagation;
+use glib::{ControlFlow, Propagation, SignalHandlerId, SourceId, VariantTy};
 use gtk4::{
-    gdk::{Display, Monitor},
+    gdk::{Display, KeyEvent, ModifierType, Monitor},
+    gio::{SimpleAction, Subprocess, SubprocessFlags},
     prelude::*,
-    {Align, Application, ApplicationWindow, EventControllerKey, GestureClick, Grid, PropagationPhase},
+    {Align, Application, ApplicationWindow, Box, Button, EventControllerKey, EventControllerMotion, GestureClick, Grid, GridLayoutChild, Label, Orientation, PropagationPhase, Widget},
 };
+use std::{cell::{Cell, RefCell}, ffi::OsStr, rc::Rc, time::Duration};
 use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
 use thiserror::Error;
 
 use crate::{
     command_button::CommandButtonParamsError,
+    keybind::{self, KeyPress},
+    logind,
     options::Options,
-    settings::{self, Settings},
+    settings::{self, Anchor, ButtonCondition, ButtonInfo, MonitorMode, PrintMode, Settings, WindowLayer},
     CommandButton,
 };
 
+const FOLLOW_CURSOR: &str = "follow-cursor";
+const CONFIRMATION_VIEW_NAME: &str = "confirmation";
+const CONFIRM_YES_NAME: &str = "confirm-yes";
+const CONFIRM_NO_NAME: &str = "confirm-no";
+const BACK_BUTTON_NAME: &str = "back";
+
 #[derive(Error, Debug)]
 pub enum ApplicationError {
-  #[error("Error occurred loading application settings")]
+  #[error("Error occurred loading application settings: {0}")]
   LoadSettingsError(#[from] settings::LoadSettingsError),
 
   #[error("Error occurred loading buttons")]
@@ -37,36 +47,268 @@ fn mouse_clicked(gesture: &GestureClick, app: &Application, _: i32, x: f64, y: f
   }
 }
 
-fn key_pressed(controller: &EventControllerKey, keyval: gtk4::gdk::Key) -> bool {
+fn key_pressed(controller: &EventControllerKey, dismiss_keys: &[String], keyval: gtk4::gdk::Key, state: ModifierType) -> bool {
   let Some(widget) = controller.widget() else { return false };
   let Some(window) = widget.downcast_ref::<ApplicationWindow>() else { return false };
   let Some(app) = window.application() else { return false };
 
-  if keyval == Key::Escape {
+  if let Some(confirmation_view) = window.child().filter(|child| child.widget_name() == CONFIRMATION_VIEW_NAME) {
+    return confirmation_key_pressed(&confirmation_view, keyval);
+  }
+
+  let press = controller
+    .current_event()
+    .and_then(|event| event.downcast::<KeyEvent>().ok())
+    .map(|event| KeyPress::from_event(&event))
+    .unwrap_or(KeyPress { keyval, state, consumed: ModifierType::empty(), unmodified: None });
+
+  if dismiss_keys.iter().any(|accelerator| keybind::matches(accelerator, &press)) {
     app.quit();
     return true;
   }
 
+  if keyval == Key::BackSpace {
+    let back_button = window.child()
+      .and_then(|page| find_child_by_name(&page, BACK_BUTTON_NAME))
+      .and_downcast::<Button>();
+
+    if let Some(back_button) = back_button {
+      back_button.emit_clicked();
+      return true;
+    }
+  }
+
   let buttons = get_command_buttons(window);
 
-  for button in buttons {
-    if let Some(keychar) = keyval.name() {
-      if button.keybind().to_string() == keychar {
-        button.clicked();
-        return true;
+  for button in buttons.iter().filter(|button| button.is_sensitive()) {
+    if button.matches_keybind(&press) {
+      button.clicked();
+      return true;
+    }
+  }
+
+  false
+}
+
+fn navigation_key_pressed(content_grid: &Grid, keyval: gtk4::gdk::Key, state: ModifierType) -> bool {
+  let buttons = navigable_buttons(content_grid);
+  let count = buttons.len();
+
+  if count == 0 {
+    return false;
+  }
+
+  let focused = content_grid.root().and_then(|root| root.focus());
+  let current = buttons.iter().position(|button| focused.as_ref() == Some(button.upcast_ref::<Widget>()));
+
+  let next = match (keyval, current) {
+    (Key::Return | Key::KP_Enter | Key::ISO_Enter | Key::space | Key::KP_Space, Some(index)) => {
+      buttons[index].emit_clicked();
+      return true;
+    }
+    (Key::Home | Key::KP_Home, _) => 0,
+    (Key::End | Key::KP_End, _) => count - 1,
+    (Key::Left | Key::KP_Left | Key::ISO_Left_Tab, Some(index)) => (index + count - 1) % count,
+    (Key::Tab, Some(index)) if state.contains(ModifierType::SHIFT_MASK) => (index + count - 1) % count,
+    (Key::Right | Key::KP_Right | Key::Tab, Some(index)) => (index + 1) % count,
+    (Key::Down | Key::KP_Down, Some(index)) => vertical_neighbour(content_grid, &buttons, index, true),
+    (Key::Up | Key::KP_Up, Some(index)) => vertical_neighbour(content_grid, &buttons, index, false),
+    (Key::Left | Key::KP_Left | Key::Right | Key::KP_Right | Key::Up | Key::KP_Up
+      | Key::Down | Key::KP_Down | Key::Tab | Key::ISO_Left_Tab, None) => 0,
+    _ => return false,
+  };
+
+  buttons[next].grab_focus();
+  true
+}
+
+/// Visible and sensitive buttons in reading order, which is the order keyboard navigation moves through.
+fn navigable_buttons(content_grid: &Grid) -> Vec<Button> {
+  let mut buttons = Vec::<(i32, i32, Button)>::new();
+  let mut current = content_grid.first_child();
+
+  while let Some(widget) = current {
+    if let Some(button) = widget.downcast_ref::<Button>() {
+      if button.is_visible() && button.is_sensitive() {
+        let (column, row, _, _) = content_grid.query_child(button);
+        buttons.push((row, column, button.clone()));
       }
     }
+    current = widget.next_sibling();
+  }
+
+  buttons.sort_by_key(|(row, column, _)| (*row, *column));
+  buttons.into_iter().map(|(_, _, button)| button).collect()
+}
+
+/// Column, row, width and height of a child in the grid.
+type GridArea = (i32, i32, i32, i32);
+
+/// The closest button in the next row up or down that covers the focused button's column, buttons can span several
+/// rows and columns so this works from grid positions rather than indexes.  Wraps to the opposite edge.
+fn vertical_neighbour(content_grid: &Grid, buttons: &[Button], index: usize, down: bool) -> usize {
+  let areas: Vec<GridArea> = buttons.iter().map(|button| content_grid.query_child(button)).collect();
+  let (column, row, _, height) = areas[index];
+
+  let closest = |in_direction: &dyn Fn(&GridArea) -> bool, edge: &dyn Fn(&GridArea) -> i32| {
+    areas.iter()
+      .enumerate()
+      .filter(|(_, area)| in_direction(area))
+      .min_by_key(|(_, area)| (edge(area), column_distance(area, column)))
+      .map(|(index, _)| index)
+  };
+
+  if down {
+    closest(&|area| area.1 >= row + height, &|area| area.1)
+      .or_else(|| closest(&|_| true, &|area| area.1))
+      .unwrap_or(index)
+  } else {
+    closest(&|area| area.1 + area.3 <= row, &|area| -(area.1 + area.3))
+      .or_else(|| closest(&|_| true, &|area| -(area.1 + area.3)))
+      .unwrap_or(index)
+  }
+}
+
+fn column_distance(&(area_column, _, area_width, _): &GridArea, column: i32) -> i32 {
+  if column < area_column {
+    area_column - column
+  } else {
+    (column - (area_column + area_width - 1)).max(0)
+  }
+}
+
+/// Mirrors keyboard focus into a "focused" class, the :focus state is only applied in the active window.
+fn track_focused_button(window: &ApplicationWindow) {
+  window.connect_focus_widget_notify(|window| {
+    let mut current = window.child().and_then(|page| page.first_child());
+
+    while let Some(widget) = current {
+      widget.remove_css_class("focused");
+      current = widget.next_sibling();
+    }
+
+    if let Some(button) = GtkWindowExt::focus(window).and_downcast::<Button>() {
+      button.add_css_class("focused");
+    }
+  });
+}
+
+fn focus_initial_button(settings: &Settings, window: &ApplicationWindow) {
+  let Some(content_grid) = window.child().and_downcast::<Grid>() else { return };
+  let buttons = navigable_buttons(&content_grid);
+
+  let initial_button = settings.initial_focus
+    .as_ref()
+    .and_then(|id| buttons.iter().find(|button| button.widget_name() == id.as_str()))
+    .or(buttons.first());
+
+  if let Some(button) = initial_button {
+    button.grab_focus();
+  }
+}
+
+fn confirmation_key_pressed(confirmation_view: &Widget, keyval: gtk4::gdk::Key) -> bool {
+  let answer_name = match keyval {
+    Key::y | Key::Y => CONFIRM_YES_NAME,
+    Key::n | Key::N | Key::Escape => CONFIRM_NO_NAME,
+    _ => return false,
+  };
+
+  if let Some(answer) = find_child_by_name(confirmation_view, answer_name) {
+    if let Some(answer_button) = answer.downcast_ref::<Button>() {
+      answer_button.emit_clicked();
+      return true;
+    }
   }
 
   false
 }
 
+fn find_child_by_name(widget: &Widget, name: &str) -> Option<Widget> {
+  let mut current = widget.first_child();
+
+  while let Some(child) = current {
+    if child.widget_name() == name {
+      return Some(child);
+    }
+
+    if let Some(found) = find_child_by_name(&child, name) {
+      return Some(found);
+    }
+
+    current = child.next_sibling();
+  }
+
+  None
+}
+
+fn show_confirmation(window: &ApplicationWindow, command_button: &CommandButton) {
+  let Some(button_grid) = window.child() else { return };
+
+  let question_label = Label::builder()
+    .label(format!("{}?", command_button.text()))
+    .css_classes(["confirm-text"])
+    .use_markup(true)
+    .build();
+
+  let yes_button = Button::builder()
+    .label("Yes")
+    .name(CONFIRM_YES_NAME)
+    .css_classes(["button", "confirm-button"])
+    .build();
+
+  let no_button = Button::builder()
+    .label("No")
+    .name(CONFIRM_NO_NAME)
+    .css_classes(["button", "confirm-button"])
+    .build();
+
+  let answer_box = Box::builder()
+    .orientation(Orientation::Horizontal)
+    .spacing(10)
+    .halign(Align::Center)
+    .build();
+
+  answer_box.append(&yes_button);
+  answer_box.append(&no_button);
+
+  let confirmation_view = Box::builder()
+    .orientation(Orientation::Vertical)
+    .spacing(20)
+    .name(CONFIRMATION_VIEW_NAME)
+    .halign(Align::Center)
+    .valign(Align::Center)
+    .build();
+
+  confirmation_view.append(&question_label);
+  confirmation_view.append(&answer_box);
+
+  let yes_window_clone = window.clone();
+  let yes_grid_clone = button_grid.clone();
+  let yes_command_button_clone = command_button.clone();
+  yes_button.connect_clicked(move |_| {
+    yes_window_clone.set_child(Some(&yes_grid_clone));
+    yes_command_button_clone.execute_command();
+  });
+
+  let no_window_clone = window.clone();
+  let no_command_button_clone = command_button.clone();
+  no_button.connect_clicked(move |_| {
+    no_window_clone.set_child(Some(&button_grid));
+    no_command_button_clone.grab_focus();
+  });
+
+  window.set_child(Some(&confirmation_view));
+  no_button.grab_focus();
+}
+
 fn get_command_buttons(window: &ApplicationWindow) -> Vec<CommandButton> {
   let mut buttons = Vec::<CommandButton>::new();
-  let mut current = window.first_child().unwrap().first_child();
+  let mut current = window.child().and_then(|page| page.first_child());
 
   while let Some(widget) = current {
-      if let Some(button) = widget.downcast_ref::<CommandButton>() {
+      // Buttons hidden by an available_if condition are left out as if they were never added.
+      if let Some(button) = widget.downcast_ref::<CommandButton>().filter(|button| button.is_visible()) {
           buttons.push(button.clone());
       }
       current = widget.next_sibling();
@@ -75,46 +317,103 @@ fn get_command_buttons(window: &ApplicationWindow) -> Vec<CommandButton> {
   buttons
 }
 
-fn init_new_window(app: &Application, monitor: Option<Monitor>, enable_keyboard: bool) -> ApplicationWindow {
+/// Anchored to every edge, duplicates in the anchor list do not count.
+fn covers_screen(settings: &Settings) -> bool {
+  [Anchor::Top, Anchor::Right, Anchor::Bottom, Anchor::Left]
+    .iter()
+    .all(|edge| settings.anchor.contains(edge))
+}
+
+fn init_layer_shell(window: &ApplicationWindow, settings: &Settings, monitor: Option<Monitor>, keyboard_mode: KeyboardMode) {
+  window.init_layer_shell();
+  window.set_namespace("curtains-close");
+
+  window.set_layer(match settings.layer {
+    WindowLayer::Top => Layer::Top,
+    WindowLayer::Overlay => Layer::Overlay,
+  });
+
+  window.set_anchor(Edge::Left, settings.anchor.contains(&Anchor::Left));
+  window.set_anchor(Edge::Right, settings.anchor.contains(&Anchor::Right));
+  window.set_anchor(Edge::Top, settings.anchor.contains(&Anchor::Top));
+  window.set_anchor(Edge::Bottom, settings.anchor.contains(&Anchor::Bottom));
+
+  window.set_margin(Edge::Left, settings.margin.left);
+  window.set_margin(Edge::Right, settings.margin.right);
+  window.set_margin(Edge::Top, settings.margin.top);
+  window.set_margin(Edge::Bottom, settings.margin.bottom);
+
+  // A full screen curtain covers panels, a smaller menu stays clear of them so it can sit below a bar.
+  if covers_screen(settings) {
+    window.set_exclusive_zone(-1);
+  } else {
+    window.set_exclusive_zone(0);
+  }
+
+  window.set_default_size(settings.width.unwrap_or(-1), settings.height.unwrap_or(-1));
+
+  if let Some(mon) = &monitor {
+    window.set_monitor(mon);
+  }
+
+  window.set_keyboard_mode(keyboard_mode);
+}
+
+/// Used on compositors without zwlr_layer_shell, the window is fullscreen unless a smaller anchored menu was configured.
+fn init_toplevel(window: &ApplicationWindow, settings: &Settings, monitor: Option<Monitor>) {
+  window.set_decorated(false);
+
+  if covers_screen(settings) {
+    match &monitor {
+      Some(mon) => window.fullscreen_on_monitor(mon),
+      None => window.fullscreen(),
+    }
+  } else {
+    window.set_default_size(settings.width.unwrap_or(-1), settings.height.unwrap_or(-1));
+  }
+}
+
+fn use_layer_shell(settings: &Settings) -> bool {
+  !settings.no_layer_shell && gtk4_layer_shell::is_supported()
+}
+
+/// Keyboard modes only exist for layer shell windows, a toplevel gets the keyboard when it is focused.
+fn set_keyboard_mode(window: &ApplicationWindow, keyboard_mode: KeyboardMode) {
+  if window.is_layer_window() {
+    window.set_keyboard_mode(keyboard_mode);
+  }
+}
+
+fn init_new_window(app: &Application, settings: &Settings, monitor: Option<Monitor>, keyboard_mode: KeyboardMode) -> ApplicationWindow {
   let new_window= ApplicationWindow::builder()
     .application(app)
     .title("curtains-close")
     .build();
-  
-  new_window.init_layer_shell();
-  new_window.set_layer(Layer::Overlay);
-  new_window.set_namespace("curtains-close");
-  new_window.set_exclusive_zone(-1);
-  new_window.set_anchor(Edge::Left, true);
-  new_window.set_anchor(Edge::Right, true);
-  new_window.set_anchor(Edge::Top, true);
-  new_window.set_anchor(Edge::Bottom, true);
-
-  if let Some(mon) = &monitor {
-    new_window.set_monitor(mon);
-  }
 
-  if enable_keyboard {
-    new_window.set_keyboard_mode(KeyboardMode::Exclusive);
+  if use_layer_shell(settings) {
+    init_layer_shell(&new_window, settings, monitor, keyboard_mode);
   } else {
-    new_window.set_keyboard_mode(KeyboardMode::None);
+    init_toplevel(&new_window, settings, monitor);
   }
 
-  let gesture = GestureClick::new();
-  let mouse_click_app_clone = app.clone();
+  if settings.dismiss_on_backdrop_click {
+    let gesture = GestureClick::new();
+    let mouse_click_app_clone = app.clone();
 
-  gesture.set_propagation_phase(PropagationPhase::Bubble);
-  gesture.set_button(gtk4::gdk::ffi::GDK_BUTTON_PRIMARY as u32);
-  gesture.connect_pressed(move |gesture, n_press , x, y |{
-    mouse_clicked(&gesture, &mouse_click_app_clone, n_press, x, y);
-  });
+    gesture.set_propagation_phase(PropagationPhase::Bubble);
+    gesture.set_button(gtk4::gdk::ffi::GDK_BUTTON_PRIMARY as u32);
+    gesture.connect_pressed(move |gesture, n_press , x, y |{
+      mouse_clicked(&gesture, &mouse_click_app_clone, n_press, x, y);
+    });
 
-  new_window.add_controller(gesture);
+    new_window.add_controller(gesture);
+  }
 
   let key_controller = EventControllerKey::new();
+  let dismiss_keys = settings.dismiss_keys.clone();
 
-  key_controller.connect_key_pressed(move |controller, keyval, _keycode, _state | {
-    if key_pressed(&controller, keyval) {
+  key_controller.connect_key_pressed(move |controller, keyval, _keycode, state | {
+    if key_pressed(&controller, &dismiss_keys, keyval, state) {
       return Propagation::Stop;
     }
 
@@ -126,7 +425,144 @@ fn init_new_window(app: &Application, monitor: Option<Monitor>, enable_keyboard:
   new_window
 }
 
+/// Probe commands that have not exited by then count as an unmet condition.
+const CONDITION_TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn is_condition_met(condition: &ButtonCondition, shell: &[String]) -> bool {
+  if let Some(action) = condition.action {
+    return logind::is_available(action).await;
+  }
+
+  let Some(argv) = condition.command.as_ref().map(|command| command.argv(shell)) else { return true };
+  let argv: Vec<&OsStr> = argv.iter().map(OsStr::new).collect();
+
+  let Ok(subprocess) = Subprocess::newv(&argv, SubprocessFlags::STDOUT_SILENCE | SubprocessFlags::STDERR_SILENCE) else { return false };
+
+  let timeout_subprocess = subprocess.downgrade();
+  glib::timeout_add_local_once(CONDITION_TIMEOUT, move || {
+    if let Some(subprocess) = timeout_subprocess.upgrade() {
+      subprocess.force_exit();
+    }
+  });
+
+  subprocess.wait_future().await.is_ok() && subprocess.is_successful()
+}
+
+/// Moves the shown buttons of a page into the places they would have had if the hidden ones were never there.
+fn relayout_page(content_grid: &Grid, buttons: &[(ButtonInfo, CommandButton)], buttons_per_row: u32) {
+  let shown: Vec<&(ButtonInfo, CommandButton)> = buttons.iter().filter(|(_, button)| button.is_visible()).collect();
+  let Ok(placements) = settings::place_buttons(shown.iter().map(|(button_info, _)| button_info), buttons_per_row) else { return };
+  let Some(layout_manager) = content_grid.layout_manager() else { return };
+
+  for ((_, button), placement) in shown.into_iter().zip(placements) {
+    let Ok(layout_child) = layout_manager.layout_child(button).downcast::<GridLayoutChild>() else { continue };
+
+    layout_child.set_column(placement.column as i32);
+    layout_child.set_row(placement.row as i32);
+    layout_child.set_column_span(placement.width as i32);
+    layout_child.set_row_span(placement.height as i32);
+  }
+}
+
+/// Conditions can take a while to answer, so the page is shown first and buttons are hidden or disabled once they do.
+fn resolve_conditions(content_grid: &Grid, buttons: Rc<Vec<(ButtonInfo, CommandButton)>>, settings: &Settings) {
+  for (button_info, button) in buttons.iter() {
+    let Some(condition) = button_info.available_if.clone() else { continue };
+
+    let shell = settings.shell.clone();
+    let buttons_per_row = settings.buttons_per_row;
+    let condition_grid_clone = content_grid.clone();
+    let condition_buttons_clone = buttons.clone();
+    let condition_button_clone = button.clone();
+    glib::spawn_future_local(async move {
+      if is_condition_met(&condition, &shell).await {
+        return;
+      }
+
+      if !condition.hide {
+        condition_button_clone.set_sensitive(false);
+        condition_button_clone.add_css_class("unavailable");
+        return;
+      }
+
+      let had_focus = condition_button_clone.has_focus();
+      condition_button_clone.set_visible(false);
+      relayout_page(&condition_grid_clone, &condition_buttons_clone, buttons_per_row);
+
+      if had_focus {
+        if let Some(button) = navigable_buttons(&condition_grid_clone).first() {
+          button.grab_focus();
+        }
+      }
+    });
+  }
+}
+
+fn button_rows(content_grid: &Grid) -> i32 {
+  let mut rows = 0;
+  let mut current = content_grid.first_child();
+
+  while let Some(widget) = current {
+    if widget.is::<CommandButton>() {
+      let (_, row, _, height) = content_grid.query_child(&widget);
+      rows = rows.max(row + height);
+    }
+    current = widget.next_sibling();
+  }
+
+  rows
+}
+
 fn load_buttons(settings: &Settings, window: &ApplicationWindow) -> Result<(), ApplicationError> {
+  let content_grid = build_page(settings, &settings.buttons, window)?;
+  window.set_child(Some(&content_grid));
+
+  Ok(())
+}
+
+/// Replaces the current page with the children of a button, the back button or Backspace return to it.
+fn show_page(settings: &Settings, buttons: &[ButtonInfo], window: &ApplicationWindow, opener: &CommandButton) {
+  let Some(parent_page) = window.child() else { return };
+
+  let page = match build_page(settings, buttons, window) {
+    Ok(page) => page,
+    Err(e) => {
+      eprintln!("Error loading buttons: {:?}", e);
+      return;
+    }
+  };
+
+  let back_button = Button::builder()
+    .label("Back")
+    .name(BACK_BUTTON_NAME)
+    .css_classes(["button", "back-button"])
+    .build();
+
+  page.attach(&back_button, 0, button_rows(&page), 1, 1);
+
+  let back_window_clone = window.clone();
+  let back_opener_clone = opener.clone();
+  back_button.connect_clicked(move |_| {
+    back_window_clone.set_child(Some(&parent_page));
+    back_opener_clone.grab_focus();
+  });
+
+  window.set_child(Some(&page));
+
+  if let Some(button) = navigable_buttons(&page).first() {
+    button.grab_focus();
+  }
+}
+
+fn print_output(button_info: &ButtonInfo, mode: PrintMode) -> String {
+  match (mode, &button_info.command, button_info.action) {
+    (PrintMode::Command, Some(command), _) => command.display(),
+    (PrintMode::Command, None, Some(action)) => action.to_string(),
+    _ => button_info.id.clone(),
+  }
+}
+
+fn build_page(settings: &Settings, buttons: &[ButtonInfo], window: &ApplicationWindow) -> Result<Grid, ApplicationError> {
   let content_grid = Grid::builder()
     .column_spacing(settings.column_spacing as i32)
     .row_spacing(settings.row_spacing as i32)
@@ -135,12 +571,18 @@ fn load_buttons(settings: &Settings, window: &ApplicationWindow) -> Result<(), A
   content_grid.set_halign(Align::Center);
   content_grid.set_valign(Align::Center);
 
-  let mut current_column = 0;
-  let mut current_row = 0;
-
   let app = window.application().unwrap();
 
-  for button_info in settings.buttons.iter() {
+  let error_label = Label::builder()
+    .css_classes(["command-error"])
+    .wrap(true)
+    .visible(false)
+    .build();
+
+  let placements = settings::place_buttons(buttons.iter(), settings.buttons_per_row)?;
+  let mut page_buttons = Vec::<(ButtonInfo, CommandButton)>::new();
+
+  for (button_info, placement) in buttons.iter().zip(placements) {
     let button_info_clone = button_info.clone();
     
     let button = CommandButton::with_params(
@@ -148,24 +590,181 @@ fn load_buttons(settings: &Settings, window: &ApplicationWindow) -> Result<(), A
      button_info_clone.id, 
      button_info_clone.icon, 
      button_info_clone.text, 
-     button_info_clone.keybind.to_string(),
-     button_info_clone.command, 
+     button_info_clone.keybind,
+     button_info_clone.command.map(|command| command.argv(&settings.shell)).unwrap_or_default(), 
      true, 
      settings.delay_before_closing
     )?;
 
-    content_grid.attach(&button, current_column, current_row, 1, 1);
-    current_column += 1;
+    button.set_icon_size(settings.icon_size);
+    button.set_description(button_info.description.clone());
+    button.set_tooltip_text(button_info.tooltip.as_deref());
+    button.set_confirm(button_info.confirm);
+    button.set_show_keybind(settings.show_keybinds);
+    button.set_print(settings.print.map(|mode| print_output(button_info, mode)));
 
-    if current_column >= settings.buttons_per_row as i32 {
-      current_column = 0;
-      current_row += 1;
+    if let Some(action) = button_info.action {
+      button.set_action(action);
+
+      let availability_button_clone = button.clone();
+      glib::spawn_future_local(async move {
+        if !logind::is_available(action).await {
+          availability_button_clone.set_sensitive(false);
+          availability_button_clone.add_css_class("unavailable");
+        }
+      });
     }
+
+    if let Some(children) = &button_info.children {
+      button.set_submenu(true);
+
+      let submenu_settings_clone = settings.clone();
+      let submenu_children_clone = children.clone();
+      let submenu_window_clone = window.clone();
+      button.connect_submenu_requested(move |button| {
+        show_page(&submenu_settings_clone, &submenu_children_clone, &submenu_window_clone, button);
+      });
+    }
+
+    let confirm_window_clone = window.clone();
+    button.connect_confirm_requested(move |button| {
+      show_confirmation(&confirm_window_clone, button);
+    });
+
+    let error_label_clone = error_label.clone();
+    button.connect_command_failed(move |_, message| {
+      error_label_clone.set_label(message);
+      error_label_clone.set_visible(true);
+    });
+
+    content_grid.attach(&button, placement.column as i32, placement.row as i32, placement.width as i32, placement.height as i32);
+    page_buttons.push((button_info.clone(), button));
   }
 
-  window.set_child(Some(&content_grid));
+  resolve_conditions(&content_grid, Rc::new(page_buttons), settings);
+
+  content_grid.attach(&error_label, 0, button_rows(&content_grid) + 1, settings.buttons_per_row.max(1) as i32, 1);
+
+  // Handled on the grid so it runs before the window's own focus movement bindings.
+  let navigation_controller = EventControllerKey::new();
+
+  navigation_controller.connect_key_pressed(|controller, keyval, _keycode, state| {
+    let Some(content_grid) = controller.widget().and_downcast::<Grid>() else { return Propagation::Proceed };
+
+    if navigation_key_pressed(&content_grid, keyval, state) {
+      return Propagation::Stop;
+    }
+
+    Propagation::Proceed
+  });
+
+  content_grid.add_controller(navigation_controller);
+
+  Ok(content_grid)
+}
+
+struct Countdown {
+  source_id: RefCell<Option<SourceId>>,
+  label: Label,
+}
+
+impl Countdown {
+  fn cancel(&self) {
+    if let Some(source_id) = self.source_id.borrow_mut().take() {
+      source_id.remove();
+      self.label.set_visible(false);
+    }
+  }
+}
+
+fn countdown_text(button: &CommandButton, remaining: u32) -> String {
+  format!("{} in {}s", button.text(), remaining)
+}
+
+fn start_countdown(settings: &Settings, window: &ApplicationWindow) -> Option<Rc<Countdown>> {
+  let default_action = settings.default_action.as_ref()?;
+  let content_grid = window.child().and_downcast::<Grid>()?;
+  let button = get_command_buttons(window).into_iter().find(|button| button.widget_name() == default_action.as_str())?;
+
+  if settings.timeout_seconds == 0 {
+    return None;
+  }
+
+  let remaining = Cell::new(settings.timeout_seconds);
+
+  let label = Label::builder()
+    .label(countdown_text(&button, remaining.get()))
+    .css_classes(["countdown"])
+    .use_markup(true)
+    .build();
+
+  content_grid.attach(&label, 0, button_rows(&content_grid), settings.buttons_per_row.max(1) as i32, 1);
+
+  let countdown = Rc::new(Countdown {
+    source_id: RefCell::new(None),
+    label: label.clone(),
+  });
+
+  let tick_countdown_clone = countdown.clone();
+  let source_id = glib::timeout_add_seconds_local(1, move || {
+    // available_if and logind can hide or disable the default button after the countdown started.
+    if !button.is_visible() || !button.is_sensitive() {
+      tick_countdown_clone.source_id.borrow_mut().take();
+      label.set_visible(false);
+      return ControlFlow::Break;
+    }
+
+    remaining.set(remaining.get().saturating_sub(1));
+
+    if remaining.get() > 0 {
+      label.set_label(&countdown_text(&button, remaining.get()));
+      return ControlFlow::Continue;
+    }
+
+    tick_countdown_clone.source_id.borrow_mut().take();
+    label.set_visible(false);
+    button.execute_command();
+    ControlFlow::Break
+  });
+
+  countdown.source_id.replace(Some(source_id));
+  cancel_countdown_on_input(&countdown, window);
 
-  return Ok(())
+  let destroy_countdown_clone = countdown.clone();
+  window.connect_destroy(move |_| {
+    destroy_countdown_clone.cancel();
+  });
+
+  Some(countdown)
+}
+
+/// Any key press or pointer movement on a window showing buttons means the user is choosing one themselves.
+fn cancel_countdown_on_input(countdown: &Rc<Countdown>, window: &ApplicationWindow) {
+  let key_controller = EventControllerKey::new();
+  let key_countdown_clone = countdown.clone();
+
+  key_controller.set_propagation_phase(PropagationPhase::Capture);
+  key_controller.connect_key_pressed(move |_, _, _, _| {
+    key_countdown_clone.cancel();
+    Propagation::Proceed
+  });
+
+  window.add_controller(key_controller);
+
+  // The compositor sends a motion event when the overlay appears under a stationary
+  // pointer, so only cancel once the pointer has actually moved.
+  let motion_controller = EventControllerMotion::new();
+  let pointer_position = Cell::new(None::<(f64, f64)>);
+  let motion_countdown_clone = countdown.clone();
+
+  motion_controller.connect_motion(move |_, x, y| {
+    match pointer_position.get() {
+      Some(position) if position != (x, y) => motion_countdown_clone.cancel(),
+      _ => pointer_position.set(Some((x, y))),
+    }
+  });
+
+  window.add_controller(motion_controller);
 }
 
 fn get_monitors() -> Vec<Monitor> {
@@ -185,31 +784,563 @@ fn get_monitors() -> Vec<Monitor> {
   monitor_list
 }
 
-fn load_windows_on_monitors(focused_window: &ApplicationWindow) {
-  let app = focused_window.application().unwrap();
-  let display= Display::default().unwrap();
-  let surface = focused_window.surface().unwrap();
-  let focused_monitor = display.monitor_at_surface(&surface).unwrap();
-  let monitors = get_monitors();
+/// Matches an output by connector (i.e. "DP-1"), manufacturer, model, or manufacturer and model together.
+fn monitor_matches(monitor: &Monitor, name: &str) -> bool {
+  let connector = monitor.connector().unwrap_or_default();
+  let manufacturer = monitor.manufacturer().unwrap_or_default();
+  let model = monitor.model().unwrap_or_default();
+
+  connector == name
+    || manufacturer == name
+    || model == name
+    || format!("{} {}", manufacturer, model) == name
+}
+
+fn find_monitor(name: &str) -> Option<Monitor> {
+  get_monitors()
+    .into_iter()
+    .find(|monitor| monitor_matches(monitor, name))
+}
+
+/// The output setting takes precedence over an output named by monitor_mode.
+fn primary_output(settings: &Settings) -> Option<&str> {
+  match (&settings.output, &settings.monitor_mode) {
+    (Some(output), _) => Some(output.as_str()),
+    (None, MonitorMode::Output(name)) => Some(name.as_str()),
+    _ => None,
+  }
+}
+
+/// Buttons, keyboard navigation and focus tracking for a window that shows the menu.
+fn load_window_content(settings: &Settings, window: &ApplicationWindow) -> Result<(), ApplicationError> {
+  load_buttons(settings, window)?;
+  track_focused_button(window);
+  focus_initial_button(settings, window);
+
+  Ok(())
+}
+
+/// Tracks the overlay window opened on each monitor, so hot-plugged monitors get one and removed monitors lose theirs.
+struct MonitorWindows {
+  settings: Settings,
+  app: Application,
+  primary: RefCell<Option<Monitor>>,
+  windows: RefCell<Vec<(Monitor, ApplicationWindow)>>,
+  monitors_handler: RefCell<Option<SignalHandlerId>>,
+  countdown: RefCell<Option<Rc<Countdown>>>,
+}
+
+impl MonitorWindows {
+  fn new(settings: &Settings, app: &Application) -> Rc<Self> {
+    Rc::new(MonitorWindows {
+      settings: settings.clone(),
+      app: app.clone(),
+      primary: RefCell::new(None),
+      windows: RefCell::new(Vec::new()),
+      monitors_handler: RefCell::new(None),
+      countdown: RefCell::new(None),
+    })
+  }
+
+  fn has_primary(&self) -> bool {
+    self.primary.borrow().is_some()
+  }
+
+  /// A window without buttons is only useful as a curtain over a whole monitor, a smaller menu would leave an empty
+  /// box in the corner of every other monitor.
+  fn opens_backdrops(&self) -> bool {
+    covers_screen(&self.settings) || self.settings.monitor_mode == MonitorMode::All
+  }
+
+  /// Closes every window but the primary one, used once follow-cursor has found the monitor when there are no backdrops.
+  fn close_backdrops(&self, primary_window: &ApplicationWindow) {
+    let closed: Vec<(Monitor, ApplicationWindow)> = {
+      let mut windows = self.windows.borrow_mut();
+      let (kept, closed) = windows.drain(..).partition(|(_, window)| window == primary_window);
+      *windows = kept;
+      closed
+    };
+
+    for (_, window) in closed {
+      window.close();
+    }
+  }
+
+  fn has_window(&self, monitor: &Monitor) -> bool {
+    self.windows.borrow().iter().any(|(window_monitor, _)| window_monitor == monitor)
+  }
+
+  fn insert(&self, monitor: Monitor, window: &ApplicationWindow) {
+    if !self.has_window(&monitor) {
+      self.windows.borrow_mut().push((monitor, window.clone()));
+    }
+  }
+
+  fn set_primary(&self, monitor: Monitor, window: &ApplicationWindow) {
+    self.insert(monitor.clone(), window);
+    self.primary.replace(Some(monitor));
+  }
+
+  /// Gives a window the keyboard and the buttons, used when the primary monitor is chosen or replaced.
+  fn promote(&self, window: &ApplicationWindow) {
+    set_keyboard_mode(window, KeyboardMode::Exclusive);
+
+    if window.child().is_none() {
+      if let Err(e) = load_window_content(&self.settings, window) {
+        eprintln!("Error loading buttons on monitor: {:?}", e);
+      }
+    }
+
+    // A toplevel only gets the keyboard by being focused.
+    if !window.is_layer_window() {
+      window.present();
+    }
+  }
+
+  fn primary_window(&self) -> Option<ApplicationWindow> {
+    let primary = self.primary.borrow();
+
+    self.windows
+      .borrow()
+      .iter()
+      .find(|(monitor, _)| primary.as_ref() == Some(monitor))
+      .map(|(_, window)| window.clone())
+  }
+
+  fn choose_primary(&self, monitor: Monitor, window: &ApplicationWindow) {
+    self.set_primary(monitor, window);
+    self.promote(window);
 
-  for monitor in monitors {
-    if monitor != focused_monitor {
-      let new_window = init_new_window(&app, Some(monitor), false);
-      new_window.present();
+    if !self.opens_backdrops() {
+      self.close_backdrops(window);
     }
+
+    if self.settings.monitor_mode == MonitorMode::All {
+      let windows = self.windows.borrow().clone();
+
+      for (_, secondary_window) in windows.iter().filter(|(_, secondary_window)| secondary_window != window) {
+        self.load_secondary_content(secondary_window);
+      }
+    }
+
+    self.start_countdown(window);
   }
+
+  /// Runs the countdown on the primary window, input on any window that shows buttons cancels it.
+  fn start_countdown(&self, window: &ApplicationWindow) {
+    let Some(countdown) = start_countdown(&self.settings, window) else { return };
+
+    for (_, other_window) in self.windows.borrow().iter().filter(|(_, other_window)| other_window != window) {
+      if other_window.child().is_some() {
+        cancel_countdown_on_input(&countdown, other_window);
+      }
+    }
+
+    self.countdown.replace(Some(countdown));
+  }
+
+  fn load_secondary_content(&self, window: &ApplicationWindow) {
+    set_keyboard_mode(window, KeyboardMode::OnDemand);
+
+    if let Err(e) = load_window_content(&self.settings, window) {
+      eprintln!("Error loading buttons on monitor: {:?}", e);
+      return;
+    }
+
+    if let Some(countdown) = self.countdown.borrow().as_ref() {
+      cancel_countdown_on_input(countdown, window);
+    }
+  }
+
+  fn open_secondary_window(&self, monitor: Monitor) -> ApplicationWindow {
+    let new_window = init_new_window(&self.app, &self.settings, Some(monitor.clone()), KeyboardMode::None);
+
+    if self.has_primary() && self.settings.monitor_mode == MonitorMode::All {
+      self.load_secondary_content(&new_window);
+    }
+
+    new_window.present();
+    self.insert(monitor, &new_window);
+    new_window
+  }
+
+  /// Opens windows on monitors that do not have one and closes the windows of monitors that are gone.
+  fn sync(&self) {
+    let monitors = get_monitors();
+    let mut opened = false;
+
+    if self.opens_backdrops() {
+      for monitor in monitors.iter() {
+        if !self.has_window(monitor) {
+          self.open_secondary_window(monitor.clone());
+          opened = true;
+        }
+      }
+    }
+
+    // Compositors without layer shell may focus the toplevels just opened, give the keyboard back to the buttons.
+    if opened {
+      if let Some(window) = self.primary_window().filter(|window| !window.is_layer_window()) {
+        window.present();
+      }
+    }
+
+    let removed: Vec<(Monitor, ApplicationWindow)> = {
+      let mut windows = self.windows.borrow_mut();
+      let (kept, removed) = windows.drain(..).partition(|(monitor, _)| monitors.contains(monitor));
+      *windows = kept;
+      removed
+    };
+
+    let primary_removed = removed
+      .iter()
+      .any(|(monitor, _)| self.primary.borrow().as_ref() == Some(monitor));
+
+    for (_, window) in removed {
+      window.close();
+    }
+
+    if primary_removed {
+      let mut replacement = self.windows.borrow().first().cloned();
+
+      // Without backdrops there is no other window to move the menu to.
+      if replacement.is_none() {
+        replacement = monitors.first().map(|monitor| (monitor.clone(), self.open_secondary_window(monitor.clone())));
+      }
+
+      if let Some((monitor, window)) = replacement {
+        self.primary.replace(Some(monitor));
+        self.promote(&window);
+      }
+    }
+  }
+
+  fn watch_monitors(self: &Rc<Self>) {
+    let Some(display) = Display::default() else { return };
+    let monitor_windows = self.clone();
+
+    let handler = display.monitors().connect_items_changed(move |_, _, _, _| {
+      monitor_windows.sync();
+    });
+
+    self.monitors_handler.replace(Some(handler));
+  }
+
+  /// Stops following monitor changes, used when the windows are replaced by a reload.
+  fn unwatch_monitors(&self) {
+    let Some(handler) = self.monitors_handler.take() else { return };
+
+    if let Some(display) = Display::default() {
+      display.monitors().disconnect(handler);
+    }
+  }
+}
+
+/// Opens a backdrop on every monitor and puts the menu on the first one the pointer enters.
+/// Wayland clients cannot query the global pointer position, so the enter event is the only way to find it.
+fn load_windows_following_cursor(monitor_windows: &Rc<MonitorWindows>) {
+  for monitor in get_monitors() {
+    let window = monitor_windows.open_secondary_window(monitor.clone());
+    let motion_controller = EventControllerMotion::new();
+    let enter_monitor_windows_clone = monitor_windows.clone();
+
+    motion_controller.connect_enter(move |controller, _, _| {
+      let Some(window) = controller.widget().and_downcast::<ApplicationWindow>() else { return };
+
+      if !enter_monitor_windows_clone.has_primary() {
+        enter_monitor_windows_clone.choose_primary(monitor.clone(), &window);
+      }
+    });
+
+    window.add_controller(motion_controller);
+  }
+
+  // Fall back to the first monitor if the compositor never reports the pointer entering a window.
+  let fallback_monitor_windows_clone = monitor_windows.clone();
+  glib::timeout_add_local_once(Duration::from_millis(500), move || {
+    if fallback_monitor_windows_clone.has_primary() {
+      return;
+    }
+
+    let first = fallback_monitor_windows_clone.windows.borrow().first().cloned();
+
+    if let Some((monitor, window)) = first {
+      fallback_monitor_windows_clone.choose_primary(monitor, &window);
+    }
+  });
+
+  monitor_windows.watch_monitors();
+}
+
+/// The command button with the given id on the page each window is currently showing.
+fn find_command_button(app: &Application, id: &str) -> Option<(ApplicationWindow, CommandButton)> {
+  app.windows()
+    .into_iter()
+    .filter_map(|window| window.downcast::<ApplicationWindow>().ok())
+    .find_map(|window| {
+      let button = get_command_buttons(&window).into_iter().find(|button| button.widget_name() == id)?;
+      Some((window, button))
+    })
+}
+
+/// Actions exported on the application ID so scripts and status bars can control the open menu over D-Bus.
+/// They are replaced each time the windows are loaded so they always refer to the current settings.
+fn add_actions(options: &Options, monitor_windows: &Rc<MonitorWindows>) {
+  add_button_actions(&monitor_windows.app);
+  add_reload_action(options, monitor_windows);
+}
+
+/// The actions that act on the buttons and windows that are open, without needing the settings.
+fn add_button_actions(app: &Application) {
+  let activate_action = SimpleAction::new("activate-button", Some(VariantTy::STRING));
+  let activate_app_clone = app.clone();
+  activate_action.connect_activate(move |_, parameter| {
+    let Some(id) = parameter.and_then(|parameter| parameter.str()) else { return };
+
+    match find_command_button(&activate_app_clone, id) {
+      Some((_, button)) if button.is_sensitive() => button.emit_clicked(),
+      Some(_) => eprintln!("Button '{}' is not available", id),
+      None => eprintln!("Button '{}' is not shown", id),
+    }
+  });
+
+  let focus_action = SimpleAction::new("focus-button", Some(VariantTy::STRING));
+  let focus_app_clone = app.clone();
+  focus_action.connect_activate(move |_, parameter| {
+    let Some(id) = parameter.and_then(|parameter| parameter.str()) else { return };
+
+    match find_command_button(&focus_app_clone, id) {
+      Some((window, button)) => {
+        window.present();
+        button.grab_focus();
+      }
+      None => eprintln!("Button '{}' is not shown", id),
+    }
+  });
+
+  let close_action = SimpleAction::new("close", None);
+  let close_app_clone = app.clone();
+  close_action.connect_activate(move |_, _| {
+    close_app_clone.quit();
+  });
+
+  app.add_action(&activate_action);
+  app.add_action(&focus_action);
+  app.add_action(&close_action);
+}
+
+/// Reloads the settings into new windows, the open menu stays if they fail to load.
+fn add_reload_action(options: &Options, monitor_windows: &Rc<MonitorWindows>) {
+  let reload_action = SimpleAction::new("reload", None);
+  let reload_options_clone = options.clone();
+  let reload_monitor_windows_clone = monitor_windows.clone();
+  reload_action.connect_activate(move |_, _| {
+    let app = &reload_monitor_windows_clone.app;
+    let old_windows = app.windows();
+
+    // The new windows are opened before the old ones close so the application never runs out of windows and exits.
+    match app_main(&reload_options_clone, app) {
+      Ok(()) => {
+        reload_monitor_windows_clone.unwatch_monitors();
+
+        for window in old_windows {
+          window.close();
+        }
+      }
+      Err(e) => eprintln!("Error reloading settings: {:?}", e),
+    }
+  });
+
+  monitor_windows.app.add_action(&reload_action);
+}
+
+/// Opens or closes the menu for a command line, toggling by default so running curtains-close again while it is open
+/// closes it instead of stacking a second menu.
+pub fn command_line_main(options: &Options, app: &Application) -> Result<(), ApplicationError> {
+  let is_open = !app.windows().is_empty();
+  let open = if options.open || options.close { options.open } else { !is_open };
+
+  match (open, is_open) {
+    (true, false) => app_main(options, app)?,
+    (true, true) => {
+      if let Some(window) = app.active_window() {
+        window.present();
+      }
+    }
+    (false, _) => app.quit(),
+  }
+
+  Ok(())
 }
 
 pub fn app_main(options: &Options, app: &Application) -> Result<(), ApplicationError> {
   let settings= Settings::load_settings(&options)?;
-  let focused_window = init_new_window(&app, None, true);
+  let monitor_windows = MonitorWindows::new(&settings, app);
 
-  focused_window.connect_is_active_notify(|window| {
-    load_windows_on_monitors(&window);
+  if primary_output(&settings) == Some(FOLLOW_CURSOR) {
+    load_windows_following_cursor(&monitor_windows);
+    add_actions(options, &monitor_windows);
+    return Ok(());
+  }
+
+  let primary_monitor = primary_output(&settings).and_then(|name| {
+    let monitor = find_monitor(name);
+
+    if monitor.is_none() {
+      eprintln!("Output '{}' not found, showing buttons on the focused monitor", name);
+    }
+
+    monitor
   });
 
-  load_buttons(&settings, &focused_window)?;
+  let focused_window = init_new_window(&app, &settings, primary_monitor, KeyboardMode::Exclusive);
+  add_actions(options, &monitor_windows);
+
+  let active_monitor_windows_clone = monitor_windows.clone();
+
+  // The monitor is only known once the compositor has placed the window.  is-active also changes
+  // whenever focus moves to a window on another monitor, so only the first notification counts.
+  focused_window.connect_is_active_notify(move |window| {
+    if active_monitor_windows_clone.has_primary() {
+      return;
+    }
+
+    let Some(surface) = window.surface() else { return };
+    let Some(monitor) = Display::default().and_then(|display| display.monitor_at_surface(&surface)) else { return };
+
+    active_monitor_windows_clone.set_primary(monitor, window);
+    active_monitor_windows_clone.sync();
+    active_monitor_windows_clone.watch_monitors();
+  });
+
+  load_window_content(&settings, &focused_window)?;
+  monitor_windows.start_countdown(&focused_window);
   focused_window.present();  
 
   Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use clap::Parser;
+  use gtk4::gio::Cancellable;
+  use std::{fs, process};
+
+  fn command_button(app: &Application, id: &str) -> CommandButton {
+    CommandButton::with_params(app, id, "", id, Vec::new(), Vec::new(), false, 0).unwrap()
+  }
+
+  fn count_clicks(button: &CommandButton) -> Rc<Cell<u32>> {
+    let clicks = Rc::new(Cell::new(0));
+    let clicks_clone = clicks.clone();
+    button.connect_clicked(move |_| clicks_clone.set(clicks_clone.get() + 1));
+    clicks
+  }
+
+  fn activate(app: &Application, name: &str, id: Option<&str>) {
+    let parameter = id.map(|id| id.to_variant());
+    app.lookup_action(name).unwrap().activate(parameter.as_ref());
+  }
+
+  fn menu_settings(id: &str) -> String {
+    format!(r#"{{"buttons": [{{"id": "{}", "icon": "", "text": "", "command": "true"}}]}}"#, id)
+  }
+
+  fn check_button_actions(app: &Application) {
+    add_button_actions(app);
+
+    let window = ApplicationWindow::new(app);
+    let page = Grid::new();
+    let shown = command_button(app, "shown");
+    let focused = command_button(app, "focused");
+    let hidden = command_button(app, "hidden");
+    let insensitive = command_button(app, "insensitive");
+
+    for (column, button) in [&shown, &focused, &hidden, &insensitive].into_iter().enumerate() {
+      page.attach(button, column as i32, 0, 1, 1);
+    }
+
+    hidden.set_visible(false);
+    insensitive.set_sensitive(false);
+    window.set_child(Some(&page));
+    window.present();
+
+    let shown_clicks = count_clicks(&shown);
+    let hidden_clicks = count_clicks(&hidden);
+    let insensitive_clicks = count_clicks(&insensitive);
+
+    activate(app, "activate-button", Some("shown"));
+    activate(app, "activate-button", Some("hidden"));
+    activate(app, "activate-button", Some("insensitive"));
+    activate(app, "activate-button", Some("missing"));
+
+    assert_eq!((shown_clicks.get(), hidden_clicks.get(), insensitive_clicks.get()), (1, 0, 0));
+
+    activate(app, "focus-button", Some("focused"));
+    assert_eq!(GtkWindowExt::focus(&window), Some(focused.clone().upcast()));
+
+    activate(app, "focus-button", Some("hidden"));
+    activate(app, "focus-button", Some("missing"));
+    assert_eq!(GtkWindowExt::focus(&window), Some(focused.upcast()));
+
+    window.destroy();
+  }
+
+  fn check_reload(app: &Application) {
+    let path = std::env::temp_dir().join(format!("curtains-close-reload-{}.json", process::id()));
+    fs::write(&path, menu_settings("before-reload")).unwrap();
+
+    let options = Options::parse_from(["curtains-close", "--no-layer-shell", "--settings-path", path.to_str().unwrap()]);
+    app_main(&options, app).unwrap();
+
+    let old_windows = app.windows();
+    fs::write(&path, menu_settings("after-reload")).unwrap();
+    activate(app, "reload", None);
+
+    let new_windows = app.windows();
+    assert!(old_windows.iter().all(|window| !new_windows.contains(window)));
+    assert!(find_command_button(app, "before-reload").is_none());
+    assert!(find_command_button(app, "after-reload").is_some());
+
+    fs::write(&path, "{").unwrap();
+    activate(app, "reload", None);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(app.windows(), new_windows);
+  }
+
+  fn check_close(app: &Application) {
+    let timed_out = Rc::new(Cell::new(false));
+    let timeout_clone = timed_out.clone();
+    let timeout_app_clone = app.clone();
+
+    // The open windows keep the application running, so only the close action can end the run before the timeout.
+    glib::timeout_add_local_once(Duration::from_secs(5), move || {
+      timeout_clone.set(true);
+      timeout_app_clone.quit();
+    });
+
+    activate(app, "close", None);
+    app.connect_activate(|_| {});
+    app.run_with_args::<&str>(&[]);
+
+    assert!(!timed_out.get());
+  }
+
+  /// GTK only works on the thread that initialized it, so every action is checked in the same test.
+  #[test]
+  fn actions_control_the_open_menu() {
+    // Windows need a display, without one there is nothing to check.
+    if gtk4::init().is_err() {
+      return;
+    }
+
+    let app = Application::builder().build();
+    app.register(None::<&Cancellable>).unwrap();
+
+    check_button_actions(&app);
+    check_reload(&app);
+    check_close(&app);
+  }
+}
@@ -1,16 +1,27 @@
 use glib::ParamSpec;
+use glib::SignalHandlerId;
 use glib::Value;
+use glib::subclass::Signal;
 use glib::subclass::prelude::*;
 use gtk4::prelude::*;
 use gtk4::subclass::button::ButtonImpl;
 use gtk4::subclass::widget::WidgetImpl;
-use gtk4::{Application, Box, Button};
+use gtk4::gio::{self, InputStream, Subprocess, SubprocessFlags};
+use gtk4::{accessible, pango, Application, Box, Button, IconTheme, Image, Widget};
 use gtk4::{subclass::widget::WidgetImplExt, Align, Label, Orientation};
 use once_cell::sync::Lazy;
-use std::cell::RefCell;
-use std::{process::Command, thread, time::Duration};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::io::{self, Write};
+use std::{ffi::OsStr, path::PathBuf, time::Duration};
 use thiserror::Error;
 
+use crate::keybind::{self, KeyPress};
+use crate::logind::{self, LogindAction};
+
+const STDERR_GRACE_PERIOD: Duration = Duration::from_millis(500);
+const STDERR_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 glib::wrapper! {
   pub struct CommandButton(ObjectSubclass<imp::CommandButton>)
     @extends Button, gtk4::Widget,
@@ -29,8 +40,8 @@ impl CommandButton {
     widget_name: S, 
     icon: S, 
     text: S, 
-    keybind: S, 
-    command: S, 
+    keybind: Vec<String>, 
+    command: Vec<String>, 
     terminate_on_click: bool, 
     terminate_delay: u32
   ) -> Result<Self, CommandButtonParamsError>
@@ -42,8 +53,8 @@ impl CommandButton {
     command_button.set_widget_name(&widget_name.into());
     command_button.set_text(text.into());
     command_button.set_icon(icon.into());
-    command_button.set_keybind(keybind.into());
-    command_button.set_command(command.into());
+    command_button.set_keybind(keybind);
+    command_button.set_command(command);
     command_button.set_terminate_on_click(terminate_on_click);
     command_button.set_terminate_delay(terminate_delay);
 
@@ -66,14 +77,26 @@ impl CommandButton {
     self.property_value("text").get::<String>().unwrap_or_default()
   }
 
-  pub fn keybind(&self) -> String {
-    self.property_value("keybind").get::<String>().unwrap_or_default()
+  pub fn icon_size(&self) -> i32 {
+    self.property_value("icon-size").get::<i32>().unwrap_or_default()
   }
 
-  pub fn command(&self) -> String {
+  pub fn description(&self) -> Option<String> {
+    self.property_value("description").get::<Option<String>>().ok().flatten()
+  }
+
+  pub fn keybind(&self) -> Vec<String> {
+    self.property_value("keybind").get::<Vec<String>>().unwrap_or_default()
+  }
+
+  pub fn matches_keybind(&self, press: &KeyPress) -> bool {
+    self.keybind().iter().any(|accelerator| keybind::matches(accelerator, press))
+  }
+
+  pub fn command(&self) -> Vec<String> {
     self
       .property_value("command")
-      .get::<String>()
+      .get::<Vec<String>>()
       .unwrap_or_default()
   }
 
@@ -84,6 +107,40 @@ impl CommandButton {
       .unwrap_or_default()
   }
 
+  pub fn action(&self) -> Option<LogindAction> {
+    self
+      .property_value("action")
+      .get::<Option<String>>()
+      .ok()
+      .flatten()
+      .and_then(|action| action.parse().ok())
+  }
+
+  pub fn print(&self) -> Option<String> {
+    self.property_value("print").get::<Option<String>>().ok().flatten()
+  }
+
+  pub fn show_keybind(&self) -> bool {
+    self
+      .property_value("show-keybind")
+      .get::<bool>()
+      .unwrap_or_default()
+  }
+
+  pub fn submenu(&self) -> bool {
+    self
+      .property_value("submenu")
+      .get::<bool>()
+      .unwrap_or_default()
+  }
+
+  pub fn confirm(&self) -> bool {
+    self
+      .property_value("confirm")
+      .get::<bool>()
+      .unwrap_or_default()
+  }
+
   pub fn terminate_delay(&self) -> u32 {
     self
       .property_value("terminate-delay")
@@ -101,17 +158,27 @@ impl CommandButton {
     self
   }
 
+  pub fn set_icon_size(&self, icon_size: i32) -> &Self {
+    self.set_property("icon-size", icon_size);
+    self
+  }
+
   pub fn set_text(&self, text: String) -> &Self {
     self.set_property("text", text);
     self
   }
 
-  pub fn set_keybind(&self, keybind: String) -> &Self {
+  pub fn set_description(&self, description: Option<String>) -> &Self {
+    self.set_property("description", description);
+    self
+  }
+
+  pub fn set_keybind(&self, keybind: Vec<String>) -> &Self {
     self.set_property("keybind", keybind);
     self
   }
 
-  pub fn set_command(&self, command: String) -> &Self {
+  pub fn set_command(&self, command: Vec<String>) -> &Self {
     self.set_property("command", command);
     self
   }
@@ -126,32 +193,235 @@ impl CommandButton {
     self
   }
 
-  fn execute_command(&self) {
-    let cmd = self.command();
-    let mut parts = cmd.split_whitespace();
+  pub fn set_action(&self, action: LogindAction) -> &Self {
+    self.set_property("action", action.as_str());
+    self
+  }
 
-    if let Some(program) = parts.next() {
-      let args: Vec<&str> = parts.collect();
+  pub fn set_print(&self, print: Option<String>) -> &Self {
+    self.set_property("print", print);
+    self
+  }
 
-      match Command::new(program).args(args).spawn() {
-        Ok(_) => {
-          if self.terminate_on_click() {
-            self.handle_termination();
-          }
-        }
-        Err(e) => eprintln!("Failed to execute command: {}", e),
+  pub fn set_show_keybind(&self, show_keybind: bool) -> &Self {
+    self.set_property("show-keybind", show_keybind);
+    self
+  }
+
+  pub fn set_submenu(&self, submenu: bool) -> &Self {
+    self.set_property("submenu", submenu);
+    self
+  }
+
+  pub fn set_confirm(&self, confirm: bool) -> &Self {
+    self.set_property("confirm", confirm);
+    self
+  }
+
+  /// Called instead of running the command when a button with `confirm` set is clicked.
+  pub fn connect_confirm_requested<F: Fn(&Self) + 'static>(&self, f: F) -> SignalHandlerId {
+    self.connect_local("confirm-requested", false, move |values| {
+      let button = values[0].get::<Self>().unwrap();
+      f(&button);
+      None
+    })
+  }
+
+  /// Called instead of running a command when a button that opens a page of child buttons is clicked.
+  pub fn connect_submenu_requested<F: Fn(&Self) + 'static>(&self, f: F) -> SignalHandlerId {
+    self.connect_local("submenu-requested", false, move |values| {
+      let button = values[0].get::<Self>().unwrap();
+      f(&button);
+      None
+    })
+  }
+
+  /// Called when the command fails to start or exits unsuccessfully, with a message that includes its stderr.
+  pub fn connect_command_failed<F: Fn(&Self, &str) + 'static>(&self, f: F) -> SignalHandlerId {
+    self.connect_local("command-failed", false, move |values| {
+      let button = values[0].get::<Self>().unwrap();
+      let message = values[1].get::<String>().unwrap();
+      f(&button, &message);
+      None
+    })
+  }
+
+  pub fn execute_command(&self) {
+    // A --print menu runs as its own instance, so the selection can end the process with the success status.
+    if let Some(output) = self.print() {
+      let mut stdout = io::stdout().lock();
+
+      if writeln!(stdout, "{}", output).and_then(|_| stdout.flush()).is_err() {
+        std::process::exit(1);
+      }
+
+      std::process::exit(0);
+    }
+
+    if let Some(action) = self.action() {
+      self.execute_action(action);
+      return;
+    }
+
+    let command = self.command();
+
+    if command.is_empty() || self.has_css_class("running") {
+      return;
+    }
+
+    let argv: Vec<&OsStr> = command.iter().map(OsStr::new).collect();
+
+    match Subprocess::newv(&argv, SubprocessFlags::STDERR_PIPE) {
+      Ok(subprocess) => {
+        self.add_css_class("running");
+
+        let button = self.clone();
+        glib::spawn_future_local(async move {
+          button.wait_for_command(subprocess).await;
+        });
+      }
+      Err(e) => self.command_failed(format!("Failed to execute command: {}", e.message())),
+    }
+  }
+
+  fn execute_action(&self, action: LogindAction) {
+    if self.has_css_class("running") {
+      return;
+    }
+
+    self.add_css_class("running");
+
+    let button = self.clone();
+    glib::spawn_future_local(async move {
+      match logind::execute(action).await {
+        Ok(()) if button.terminate_on_click() => button.handle_termination(),
+        Ok(()) => button.remove_css_class("running"),
+        Err(e) => button.command_failed(format!("Failed to {}: {}", action, e)),
+      }
+    });
+  }
+
+  async fn wait_for_command(&self, subprocess: Subprocess) {
+    // Read stderr alongside the wait, a command that backgrounds a child can hold the pipe open after it exits.
+    let stderr = Rc::new(StderrBuffer::default());
+    let stderr_reader = subprocess.stderr_pipe().map(|pipe| glib::spawn_future_local(read_stderr(pipe, stderr.clone())));
+    let result = subprocess.wait_future().await;
+
+    if result.is_ok() && subprocess.is_successful() {
+      if let Some(reader) = stderr_reader {
+        reader.abort();
+      }
+
+      if self.terminate_on_click() {
+        self.handle_termination();
+      } else {
+        self.remove_css_class("running");
       }
+
+      return;
+    }
+
+    let reason = match result {
+      Err(e) => format!("Failed waiting for command: {}", e.message()),
+      Ok(()) if subprocess.has_exited() => format!("Command exited with status {}", subprocess.exit_status()),
+      Ok(()) => format!("Command was terminated by signal {}", subprocess.term_sig()),
+    };
+
+    // Give the rest of stderr a moment to arrive, but report with what was read so far if a background child keeps
+    // the pipe open.
+    if let Some(reader) = stderr_reader {
+      let mut waited = Duration::ZERO;
+
+      while !stderr.finished.get() && waited < STDERR_GRACE_PERIOD {
+        glib::timeout_future(STDERR_POLL_INTERVAL).await;
+        waited += STDERR_POLL_INTERVAL;
+      }
+
+      reader.abort();
+    }
+
+    let stderr = String::from_utf8_lossy(&stderr.output.borrow()).into_owned();
+
+    if stderr.trim().is_empty() {
+      self.command_failed(reason);
+    } else {
+      self.command_failed(format!("{}\n{}", reason, stderr.trim()));
     }
   }
 
+  fn command_failed(&self, message: String) {
+    eprintln!("{}", message);
+    self.remove_css_class("running");
+    self.emit_by_name::<()>("command-failed", &[&message]);
+  }
+
   fn handle_termination(&self) {
     let delay = self.terminate_delay();
+    let app = self.app();
+
     if delay > 0 {
-        thread::sleep(Duration::from_millis(delay as u64));
+      glib::timeout_add_local_once(Duration::from_millis(delay as u64), move || app.quit());
+    } else {
+      app.quit();
+    }
+  }
+}
+
+/// The text label may contain Pango markup, screen readers should only get the text itself.
+fn strip_markup(text: &str) -> String {
+  match pango::parse_markup(text, '\0') {
+    Ok((_, plain_text, _)) => plain_text.to_string(),
+    Err(_) => text.to_string(),
+  }
+}
+
+/// Icons containing a '/' are image files, names found in the icon theme are themed icons, anything else is
+/// text meant for an icon font.
+fn icon_widget(button: &CommandButton, icon: &str, icon_size: i32) -> Widget {
+  let gicon = if icon.contains('/') {
+    let path = match icon.strip_prefix("~/") {
+      Some(relative) => dirs::home_dir().unwrap_or_default().join(relative),
+      None => PathBuf::from(icon),
+    };
+
+    Some(gio::FileIcon::new(&gio::File::for_path(path)).upcast::<gio::Icon>())
+  } else if IconTheme::for_display(&button.display()).has_icon(icon) {
+    Some(gio::ThemedIcon::new(icon).upcast::<gio::Icon>())
+  } else {
+    None
+  };
+
+  match gicon {
+    Some(gicon) => Image::builder()
+      .gicon(&gicon)
+      .pixel_size(icon_size)
+      .css_classes(["button-icon"])
+      .build()
+      .upcast(),
+    None => Label::builder()
+      .label(icon)
+      .css_classes(["button-icon"])
+      .build()
+      .upcast(),
+  }
+}
+
+#[derive(Default)]
+struct StderrBuffer {
+  output: RefCell<Vec<u8>>,
+  finished: Cell<bool>,
+}
+
+async fn read_stderr(stream: InputStream, buffer: Rc<StderrBuffer>) {
+  while let Ok(bytes) = stream.read_bytes_future(4096, glib::Priority::DEFAULT).await {
+    if bytes.is_empty() {
+      break;
     }
 
-    self.app().quit();
+    buffer.output.borrow_mut().extend_from_slice(&bytes);
   }
+
+  buffer.finished.set(true);
 }
 
 mod imp {
@@ -161,11 +431,18 @@ mod imp {
   pub struct CommandButton {
     app: RefCell<Option<Application>>,
     icon: RefCell<Option<String>>,
+    icon_size: RefCell<i32>,
     text: RefCell<Option<String>>,
-    keybind: RefCell<Option<String>>,
-    command: RefCell<Option<String>>,
+    description: RefCell<Option<String>>,
+    keybind: RefCell<Vec<String>>,
+    command: RefCell<Vec<String>>,
+    action: RefCell<Option<String>>,
+    print: RefCell<Option<String>>,
     terminate_on_click: RefCell<bool>,
     terminate_delay: RefCell<u32>,
+    confirm: RefCell<bool>,
+    show_keybind: RefCell<bool>,
+    submenu: RefCell<bool>,
   }
 
   #[glib::object_subclass]
@@ -181,26 +458,54 @@ mod imp {
         vec![
           glib::ParamSpecObject::builder::<Application>("app").build(),
           glib::ParamSpecString::builder("icon").build(),
+          glib::ParamSpecInt::builder("icon-size").minimum(-1).default_value(-1).build(),
           glib::ParamSpecString::builder("text").build(),
-          glib::ParamSpecString::builder("keybind").build(),
-          glib::ParamSpecString::builder("command").build(),
+          glib::ParamSpecString::builder("description").build(),
+          glib::ParamSpecBoxed::builder::<Vec<String>>("keybind").build(),
+          glib::ParamSpecBoxed::builder::<Vec<String>>("command").build(),
+          glib::ParamSpecString::builder("action").build(),
+          glib::ParamSpecString::builder("print").build(),
           glib::ParamSpecBoolean::builder("terminate-on-click").build(),
-          glib::ParamSpecUInt::builder("terminate-delay").build()
+          glib::ParamSpecUInt::builder("terminate-delay").build(),
+          glib::ParamSpecBoolean::builder("confirm").build(),
+          glib::ParamSpecBoolean::builder("show-keybind").build(),
+          glib::ParamSpecBoolean::builder("submenu").build()
         ]
       });
       
       PROPERTIES.as_ref()
     }
 
+    fn signals() -> &'static [Signal] {
+      static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+        vec![
+          Signal::builder("confirm-requested").build(),
+          Signal::builder("submenu-requested").build(),
+          Signal::builder("command-failed")
+            .param_types([String::static_type()])
+            .build()
+        ]
+      });
+
+      SIGNALS.as_ref()
+    }
+
     fn property(&self, _id: usize, pspec: &ParamSpec) -> Value {
       match pspec.name() {
         "app" => self.app.borrow().to_value(),
         "icon" => self.icon.borrow().to_value(),
+        "icon-size" => self.icon_size.borrow().to_value(),
         "text" => self.text.borrow().to_value(),
+        "description" => self.description.borrow().to_value(),
         "keybind" => self.keybind.borrow().to_value(),
         "command" => self.command.borrow().to_value(),
+        "action" => self.action.borrow().to_value(),
+        "print" => self.print.borrow().to_value(),
         "terminate-on-click" => self.terminate_on_click.borrow().to_value(),
         "terminate-delay" => self.terminate_delay.borrow().to_value(),
+        "confirm" => self.confirm.borrow().to_value(),
+        "show-keybind" => self.show_keybind.borrow().to_value(),
+        "submenu" => self.submenu.borrow().to_value(),
         _ => unimplemented!(),
       }
     }
@@ -217,11 +522,21 @@ mod imp {
             self.icon.replace(icon);
           }
         },
+        "icon-size" => {
+          if let Ok(icon_size) = value.get() {
+            self.icon_size.replace(icon_size);
+          }
+        },
         "text" => {
           if let Ok(text) = value.get() {
             self.text.replace(text);
           }
         },
+        "description" => {
+          if let Ok(description) = value.get() {
+            self.description.replace(description);
+          }
+        },
         "keybind" => {
           if let Ok(keybind) = value.get() {
             self.keybind.replace(keybind);
@@ -232,6 +547,16 @@ mod imp {
             self.command.replace(command);
           }
         },
+        "action" => {
+          if let Ok(action) = value.get() {
+            self.action.replace(action);
+          }
+        },
+        "print" => {
+          if let Ok(print) = value.get() {
+            self.print.replace(print);
+          }
+        },
         "terminate-on-click" => {
           if let Ok(terminate_on_click) = value.get() {
             self.terminate_on_click.replace(terminate_on_click);
@@ -242,6 +567,21 @@ mod imp {
             self.terminate_delay.replace(terminate_delay);
           }
         },
+        "confirm" => {
+          if let Ok(confirm) = value.get() {
+            self.confirm.replace(confirm);
+          }
+        },
+        "show-keybind" => {
+          if let Ok(show_keybind) = value.get() {
+            self.show_keybind.replace(show_keybind);
+          }
+        },
+        "submenu" => {
+          if let Ok(submenu) = value.get() {
+            self.submenu.replace(submenu);
+          }
+        },
         _ => unimplemented!(),
       }
     }
@@ -268,12 +608,7 @@ mod imp {
       };
 
       if ! icon.is_empty() {
-          let icon_label = Label::builder()
-          .label(&icon)
-          .css_classes(["button-icon"])
-          .build();
-
-          vbox.append(&icon_label);
+          vbox.append(&icon_widget(&button, &icon, *self.icon_size.borrow()));
       }
 
       if ! text.is_empty() {
@@ -286,6 +621,32 @@ mod imp {
         vbox.append(&text_label);
       }
 
+      let keybind = self.keybind.borrow();
+
+      if *self.show_keybind.borrow() && ! keybind.is_empty() {
+        let keybind_text = keybind
+          .iter()
+          .map(|accelerator| keybind::label(accelerator))
+          .collect::<Vec<String>>()
+          .join(", ");
+
+        let keybind_label = Label::builder()
+        .label(&keybind_text)
+        .css_classes(["button-keybind"])
+        .build();
+
+        vbox.append(&keybind_label);
+      }
+
+
+      if ! text.is_empty() {
+        button.update_property(&[accessible::Property::Label(&strip_markup(&text))]);
+      }
+
+      if let Some(description) = self.description.borrow().as_deref() {
+        button.update_property(&[accessible::Property::Description(description)]);
+      }
+
       button.add_css_class("button");
       button.set_child(Some(&vbox));
     }  
@@ -294,7 +655,14 @@ mod imp {
   impl ButtonImpl for CommandButton {
     fn clicked(&self) {
       let button = self.obj();
-      button.execute_command();
+
+      if button.submenu() {
+        button.emit_by_name::<()>("submenu-requested", &[]);
+      } else if button.confirm() {
+        button.emit_by_name::<()>("confirm-requested", &[]);
+      } else {
+        button.execute_command();
+      }
     }
   }
 }
\ No newline at end of file
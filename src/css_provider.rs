@@ -22,7 +22,7 @@ pub enum LoadCSSProviderError {
     ContentOrPathNotFound
 }
 
-fn set_css_provider(css_content: &str) -> Result<(), LoadCSSProviderError> {
+fn set_css_provider(css_content: &str, priority: u32) -> Result<(), LoadCSSProviderError> {
   let provider = CssProvider::new();
   let default_display = Display::default();
 
@@ -33,7 +33,7 @@ fn set_css_provider(css_content: &str) -> Result<(), LoadCSSProviderError> {
       style_context_add_provider_for_display(
         &display,
         &provider,
-        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION
+        priority
       );
   
     })
@@ -54,50 +54,43 @@ fn set_css_provider(css_content: &str) -> Result<(), LoadCSSProviderError> {
   return Ok(());
 }
 
+/// Adds every style layer that exists: /etc, then /usr/local/etc, then the user's config, then the command line.
+/// Each layer gets a higher priority than the one before it, so later layers override earlier ones.
 pub fn add_css_provider(options: &Options) -> Result<(), LoadCSSProviderError> {
-  if let Some(css_content) = &options.css_content {
-    set_css_provider(&css_content)?;
-  }
-
-  if let Some(css_loader_path) = &options.css_loader_path {
-    let css_content = load_content_from_script(&css_loader_path)?;
-    set_css_provider(&css_content)?;
-  }
+  let mut layers = Vec::<String>::new();
 
-  if let Some(css_path) = &options.css_path {
-    let css_content = std::fs::read_to_string(css_path)?;
-    set_css_provider(&css_content)?;
+  for path in [Path::new("/etc/curtains-close/style.css"), Path::new("/usr/local/etc/curtains-close/style.css")] {
+    if path.exists() {
+      layers.push(std::fs::read_to_string(path)?);
+    }
   }
 
   if let Some(home_dir) = home_dir() {
     let curtains_config_loaderpath= home_dir.join(".config/curtains/close/style.sh");
+    let curtains_config_path = home_dir.join(".config/curtains/close/style.css");
+
     if curtains_config_loaderpath.exists() {
-      let css_content = load_content_from_script(&curtains_config_loaderpath)?;
-      set_css_provider(&css_content)?;
-      return Ok(());
+      layers.push(load_content_from_script(&curtains_config_loaderpath)?);
+    } else if curtains_config_path.exists() {
+      layers.push(std::fs::read_to_string(curtains_config_path)?);
     }
+  }
 
-    let curtains_config_path = home_dir.join(".config/curtains/close/style.css");
-    if curtains_config_path.exists() {
-      let css_content = std::fs::read_to_string(curtains_config_path)?;
-      set_css_provider(&css_content)?;
-      return Ok(());
-    }
+  if let Some(css_path) = &options.css_path {
+    layers.push(std::fs::read_to_string(css_path)?);
   }
 
-  let usr_local_path = Path::new("/usr/local/etc/curtains-close/style.css");
-  if usr_local_path.exists() {
-    let css_content = std::fs::read_to_string(usr_local_path)?;
-    set_css_provider(&css_content)?;
-    return Ok(());
+  if let Some(css_loader_path) = &options.css_loader_path {
+    layers.push(load_content_from_script(&css_loader_path)?);
   }
 
-  let etc_path = Path::new("/etc/curtains-close/style.css");
-  if etc_path.exists() {
-    let css_content = std::fs::read_to_string(etc_path)?;
-    set_css_provider(&css_content)?;
-    return Ok(());
-  }    
+  if let Some(css_content) = &options.css_content {
+    layers.push(css_content.clone());
+  }
+
+  for (index, css_content) in layers.iter().enumerate() {
+    set_css_provider(css_content, gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + index as u32)?;
+  }
 
   Ok(())
-}
\ No newline at end of file
+}
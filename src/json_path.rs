@@ -0,0 +1,229 @@
+/// Where a value starts and ends in a JSON document, with its path written as `buttons[2].keybind[0]`.
+struct ValueSpan {
+  path: String,
+  start: usize,
+  end: usize,
+}
+
+/// A lenient scanner that records the span of every value, serde_json does not keep positions once a document is parsed.
+/// It stops at the first malformed token and keeps the spans found before it.
+struct Scanner<'a> {
+  bytes: &'a [u8],
+  position: usize,
+  spans: Vec<ValueSpan>,
+}
+
+impl<'a> Scanner<'a> {
+  fn skip_whitespace(&mut self) {
+    while self.bytes.get(self.position).is_some_and(|byte| byte.is_ascii_whitespace()) {
+      self.position += 1;
+    }
+  }
+
+  fn expect(&mut self, expected: u8) -> Option<()> {
+    self.skip_whitespace();
+
+    if self.bytes.get(self.position) != Some(&expected) {
+      return None;
+    }
+
+    self.position += 1;
+    Some(())
+  }
+
+  fn string(&mut self) -> Option<String> {
+    self.expect(b'"')?;
+    let start = self.position;
+
+    loop {
+      match self.bytes.get(self.position)? {
+        b'\\' => self.position += 2,
+        b'"' => break,
+        _ => self.position += 1,
+      }
+    }
+
+    let content = String::from_utf8_lossy(&self.bytes[start..self.position]).into_owned();
+    self.position += 1;
+    Some(content)
+  }
+
+  fn value(&mut self, path: String) -> Option<()> {
+    self.skip_whitespace();
+    let start = self.position;
+    let index = self.spans.len();
+
+    self.spans.push(ValueSpan { path: path.clone(), start, end: start });
+
+    match self.bytes.get(self.position)? {
+      b'{' => self.object(&path)?,
+      b'[' => self.array(&path)?,
+      b'"' => { self.string()?; },
+      _ => {
+        while self.bytes.get(self.position).is_some_and(|byte| !b",]} \t\r\n".contains(byte)) {
+          self.position += 1;
+        }
+      }
+    }
+
+    self.spans[index].end = self.position;
+    Some(())
+  }
+
+  fn object(&mut self, path: &str) -> Option<()> {
+    self.expect(b'{')?;
+    self.skip_whitespace();
+
+    if self.bytes.get(self.position) == Some(&b'}') {
+      self.position += 1;
+      return Some(());
+    }
+
+    loop {
+      let key = self.string()?;
+      self.expect(b':')?;
+
+      if path.is_empty() {
+        self.value(key)?;
+      } else {
+        self.value(format!("{}.{}", path, key))?;
+      }
+
+      self.skip_whitespace();
+
+      match self.bytes.get(self.position)? {
+        b',' => self.position += 1,
+        b'}' => {
+          self.position += 1;
+          return Some(());
+        }
+        _ => return None,
+      }
+    }
+  }
+
+  fn array(&mut self, path: &str) -> Option<()> {
+    self.expect(b'[')?;
+    self.skip_whitespace();
+
+    if self.bytes.get(self.position) == Some(&b']') {
+      self.position += 1;
+      return Some(());
+    }
+
+    let mut index = 0;
+
+    loop {
+      self.value(format!("{}[{}]", path, index))?;
+      self.skip_whitespace();
+      index += 1;
+
+      match self.bytes.get(self.position)? {
+        b',' => self.position += 1,
+        b']' => {
+          self.position += 1;
+          return Some(());
+        }
+        _ => return None,
+      }
+    }
+  }
+}
+
+fn value_spans(json: &str) -> Vec<ValueSpan> {
+  let mut scanner = Scanner { bytes: json.as_bytes(), position: 0, spans: Vec::new() };
+  scanner.value(String::new());
+  scanner.spans
+}
+
+/// One based line and column of a byte offset, counted the same way serde_json reports them.
+fn line_column(json: &str, offset: usize) -> (usize, usize) {
+  let before = &json.as_bytes()[..offset.min(json.len())];
+  let line = before.iter().filter(|byte| **byte == b'\n').count() + 1;
+  let column = before.iter().rev().take_while(|byte| **byte != b'\n').count() + 1;
+
+  (line, column)
+}
+
+/// Line and column where the value at a path starts.
+pub fn locate(json: &str, path: &str) -> Option<(usize, usize)> {
+  value_spans(json)
+    .into_iter()
+    .find(|span| span.path == path)
+    .map(|span| line_column(json, span.start))
+}
+
+/// Path of the innermost value containing a line and column, used to name the field of a serde_json error.
+pub fn path_at(json: &str, line: usize, column: usize) -> Option<String> {
+  let line_start = json
+    .split_inclusive('\n')
+    .take(line.saturating_sub(1))
+    .map(str::len)
+    .sum::<usize>();
+  let offset = line_start + column.saturating_sub(1);
+
+  value_spans(json)
+    .into_iter()
+    .rev()
+    .find(|span| !span.path.is_empty() && span.start <= offset && offset < span.end.max(span.start + 1))
+    .map(|span| span.path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SETTINGS: &str = r#"{
+  "buttons_per_row": 3,
+  "buttons": [
+    {"id": "lock", "keybind": ["l", "<Ctrl>l"]},
+    {"id": "logout", "text": "Log \"out\" \\ leave", "keybind": ["o"]}
+  ]
+}"#;
+
+  #[test]
+  fn locates_nested_values() {
+    assert_eq!(locate(SETTINGS, "buttons_per_row"), Some((2, 22)));
+    assert_eq!(locate(SETTINGS, "buttons[0].keybind[1]"), Some((4, 37)));
+    assert_eq!(locate(SETTINGS, "buttons[1].id"), Some((5, 12)));
+    assert_eq!(locate(SETTINGS, "buttons[2]"), None);
+  }
+
+  #[test]
+  fn skips_escaped_quotes_and_backslashes() {
+    assert_eq!(locate(SETTINGS, "buttons[1].text"), Some((5, 30)));
+    assert_eq!(locate(SETTINGS, "buttons[1].keybind[0]"), Some((5, 66)));
+  }
+
+  #[test]
+  fn escaped_key_does_not_end_the_string() {
+    let json = r#"{"a\"b": 1, "c": 2}"#;
+
+    assert_eq!(locate(json, r#"a\"b"#), Some((1, 10)));
+    assert_eq!(locate(json, "c"), Some((1, 18)));
+  }
+
+  #[test]
+  fn names_the_field_of_a_type_error() {
+    let json = "{\n  \"x\": 1,\n  \"buttons_per_row\": \"3\"\n}";
+    let error = serde_json::from_str::<std::collections::HashMap<String, u32>>(json).unwrap_err();
+
+    assert_eq!(path_at(json, error.line(), error.column()).as_deref(), Some("buttons_per_row"));
+  }
+
+  #[test]
+  fn names_the_innermost_value_of_a_syntax_error() {
+    let json = "{\"buttons\": [{\"id\": \"a\", \"keybind\": [\"x\",]}]}";
+    let error = serde_json::from_str::<serde_json::Value>(json).unwrap_err();
+
+    assert_eq!(path_at(json, error.line(), error.column()).as_deref(), Some("buttons[0].keybind[1]"));
+  }
+
+  #[test]
+  fn keeps_spans_found_before_malformed_input() {
+    let json = "{\"a\": 1, \"b\": [2, }";
+
+    assert_eq!(locate(json, "a"), Some((1, 7)));
+    assert_eq!(locate(json, "b[0]"), Some((1, 16)));
+  }
+}
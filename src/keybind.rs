@@ -0,0 +1,215 @@
+use gtk4::gdk::{prelude::*, Key, KeyEvent, ModifierType};
+use glib::translate::from_glib;
+use serde::{Deserialize, Deserializer};
+use std::ffi::CString;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawKeybinds {
+  One(String),
+  Many(Vec<String>),
+}
+
+/// Accepts either a single accelerator string or a list of them.
+pub fn deserialize_keybinds<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+  D: Deserializer<'de> {
+  Ok(match RawKeybinds::deserialize(deserializer)? {
+    RawKeybinds::One(keybind) => vec![keybind],
+    RawKeybinds::Many(keybinds) => keybinds,
+  })
+}
+
+/// Rewrites the "Ctrl+Shift+S" style into the "<Ctrl><Shift>S" form understood by the GTK accelerator parser.
+fn normalize(accelerator: &str) -> String {
+  let accelerator = accelerator.trim();
+
+  if accelerator.starts_with('<') || !accelerator.contains('+') || accelerator == "+" {
+    return accelerator.to_string();
+  }
+
+  // "Ctrl++" is the plus key with a modifier, GTK only knows that key by its name.
+  let (modifiers, key) = match accelerator.strip_suffix("++") {
+    Some(modifiers) => (modifiers, "plus"),
+    None => accelerator.rsplit_once('+').unwrap_or_default(),
+  };
+
+  modifiers
+    .split('+')
+    .map(|modifier| format!("<{}>", modifier.trim()))
+    .chain(std::iter::once(key.trim().to_string()))
+    .collect()
+}
+
+/// Parses an accelerator into a lower case keyval and the modifiers that must be held.
+pub fn parse(accelerator: &str) -> Option<(Key, ModifierType)> {
+  let accelerator = CString::new(normalize(accelerator)).ok()?;
+  let mut key = 0;
+  let mut modifiers = 0;
+
+  // The safe binding asserts that GTK is initialized, but the parser only reads the keyval tables and never opens the
+  // display, so calling it directly lets --check-config run without one.
+  let parsed = unsafe { gtk4::ffi::gtk_accelerator_parse(accelerator.as_ptr(), &mut key, &mut modifiers) };
+
+  if parsed == glib::ffi::GFALSE || key == 0 {
+    return None;
+  }
+
+  let (key, modifiers): (Key, ModifierType) = unsafe { (from_glib(key), from_glib(modifiers)) };
+  Some((key.to_lower(), modifiers))
+}
+
+/// The modifiers an accelerator can ask for, the same set as gtk4::accelerator_get_default_mod_mask.
+const MODIFIER_MASK: ModifierType = ModifierType::SHIFT_MASK
+  .union(ModifierType::CONTROL_MASK)
+  .union(ModifierType::ALT_MASK)
+  .union(ModifierType::SUPER_MASK)
+  .union(ModifierType::HYPER_MASK)
+  .union(ModifierType::META_MASK);
+
+/// A key press as keybinds see it.  The keyval is what the layout typed with the held modifiers, consumed are the
+/// modifiers used up typing it, and unmodified is the keyval of the same physical key with no modifiers applied.
+#[derive(Clone, Copy, Debug)]
+pub struct KeyPress {
+  pub keyval: Key,
+  pub state: ModifierType,
+  pub consumed: ModifierType,
+  pub unmodified: Option<Key>,
+}
+
+impl KeyPress {
+  pub fn from_event(event: &KeyEvent) -> KeyPress {
+    let unmodified = event
+      .display()
+      .and_then(|display| display.translate_key(event.keycode(), ModifierType::empty(), event.layout() as i32))
+      .map(|(keyval, _, _, _)| keyval);
+
+    KeyPress {
+      keyval: event.keyval(),
+      state: event.modifier_state(),
+      consumed: event.consumed_modifiers(),
+      unmodified,
+    }
+  }
+}
+
+/// Matches like GTK shortcuts do: modifiers consumed by the layout are ignored, so "question" matches the key typed
+/// with Shift, and "<Shift>1" matches the key whose unmodified keyval is 1 even though Shift turns it into "exclam".
+pub fn matches(accelerator: &str, press: &KeyPress) -> bool {
+  let Some((key, modifiers)) = parse(accelerator) else { return false };
+  let unconsumed = MODIFIER_MASK - press.consumed;
+
+  let typed = key == press.keyval.to_lower() && modifiers & unconsumed == press.state & unconsumed;
+  let unmodified = press.unmodified.is_some_and(|keyval| key == keyval.to_lower()) && modifiers == press.state & MODIFIER_MASK;
+
+  typed || unmodified
+}
+
+/// Text shown in the keybind hint, in the same form GTK uses for accelerators in menus.
+pub fn label(accelerator: &str) -> String {
+  match parse(accelerator) {
+    Some((key, modifiers)) => gtk4::accelerator_get_label(key, modifiers).to_string(),
+    None => accelerator.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn normalize_rewrites_plus_separated_modifiers() {
+    assert_eq!(normalize("Ctrl+Shift+S"), "<Ctrl><Shift>S");
+    assert_eq!(normalize(" Alt + F4 "), "<Alt>F4");
+    assert_eq!(normalize("Ctrl++"), "<Ctrl>plus");
+  }
+
+  #[test]
+  fn normalize_keeps_gtk_syntax_and_single_keys() {
+    assert_eq!(normalize("<Control><Shift>s"), "<Control><Shift>s");
+    assert_eq!(normalize("<Ctrl>plus"), "<Ctrl>plus");
+    assert_eq!(normalize("Return"), "Return");
+    assert_eq!(normalize("+"), "+");
+  }
+
+  #[test]
+  fn parse_uses_the_gtk_accelerator_syntax() {
+    assert_eq!(parse("F1"), Some((Key::F1, ModifierType::empty())));
+    assert_eq!(parse("Return"), Some((Key::Return, ModifierType::empty())));
+    assert_eq!(parse("<Control><Shift>s"), Some((Key::s, ModifierType::CONTROL_MASK | ModifierType::SHIFT_MASK)));
+    assert_eq!(parse("<Primary>q"), Some((Key::q, ModifierType::CONTROL_MASK)));
+    assert_eq!(parse("<Alt>F4"), Some((Key::F4, ModifierType::ALT_MASK)));
+    assert_eq!(parse("<Super>l"), Some((Key::l, ModifierType::SUPER_MASK)));
+  }
+
+  #[test]
+  fn parse_accepts_the_plus_separated_form() {
+    assert_eq!(parse("Ctrl+Shift+S"), parse("<Control><Shift>s"));
+    assert_eq!(parse("Ctrl++"), Some((Key::plus, ModifierType::CONTROL_MASK)));
+  }
+
+  #[test]
+  fn parse_returns_the_lower_case_keyval() {
+    assert_eq!(parse("S"), Some((Key::s, ModifierType::empty())));
+  }
+
+  #[test]
+  fn parse_rejects_invalid_accelerators() {
+    assert_eq!(parse(""), None);
+    assert_eq!(parse("NotAKey"), None);
+    assert_eq!(parse("Ctrl+"), None);
+  }
+
+  fn press(keyval: Key, state: ModifierType, consumed: ModifierType, unmodified: Key) -> KeyPress {
+    KeyPress { keyval, state, consumed, unmodified: Some(unmodified) }
+  }
+
+  #[test]
+  fn matches_keyval_and_modifiers() {
+    let ctrl_s = press(Key::s, ModifierType::CONTROL_MASK, ModifierType::empty(), Key::s);
+
+    assert!(matches("<Control>s", &ctrl_s));
+    assert!(matches("Ctrl+S", &ctrl_s));
+    assert!(!matches("s", &ctrl_s));
+    assert!(!matches("<Control><Shift>s", &ctrl_s));
+  }
+
+  #[test]
+  fn matches_ignores_lock_and_pointer_state() {
+    let state = ModifierType::LOCK_MASK | ModifierType::BUTTON1_MASK;
+
+    assert!(matches("Return", &press(Key::Return, state, ModifierType::empty(), Key::Return)));
+  }
+
+  #[test]
+  fn matches_keys_typed_with_a_consumed_shift() {
+    let question = press(Key::question, ModifierType::SHIFT_MASK, ModifierType::SHIFT_MASK, Key::slash);
+    let azerty_one = press(Key::_1, ModifierType::SHIFT_MASK, ModifierType::SHIFT_MASK, Key::ampersand);
+
+    assert!(matches("question", &question));
+    assert!(matches("1", &azerty_one));
+    assert!(!matches("slash", &question));
+  }
+
+  #[test]
+  fn matches_shift_with_the_unmodified_key() {
+    let exclam = press(Key::exclam, ModifierType::SHIFT_MASK, ModifierType::SHIFT_MASK, Key::_1);
+
+    assert!(matches("<Shift>1", &exclam));
+    assert!(matches("exclam", &exclam));
+    assert!(!matches("1", &exclam));
+  }
+
+  #[test]
+  fn matches_keeps_modifiers_that_were_not_consumed() {
+    let ctrl_question = press(Key::question, ModifierType::CONTROL_MASK | ModifierType::SHIFT_MASK, ModifierType::SHIFT_MASK, Key::slash);
+
+    assert!(matches("<Control>question", &ctrl_question));
+    assert!(!matches("question", &ctrl_question));
+  }
+
+  #[test]
+  fn invalid_accelerators_never_match() {
+    assert!(!matches("NotAKey", &press(Key::a, ModifierType::empty(), ModifierType::empty(), Key::a)));
+  }
+}
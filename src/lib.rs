@@ -3,5 +3,8 @@ pub mod command_button;
 pub use command_button::CommandButton;
 pub mod content_loader;
 pub mod css_provider;
+pub mod json_path;
+pub mod keybind;
+pub mod logind;
 pub mod options;
 pub mod settings;
\ No newline at end of file
@@ -0,0 +1,319 @@
+use gtk4::gio::{self, BusType, DBusCallFlags, DBusConnection};
+use glib::{Variant, VariantTy};
+use serde::Deserialize;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+const LOGIND_BUS_NAME: &str = "org.freedesktop.login1";
+const MANAGER_PATH: &str = "/org/freedesktop/login1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+const SESSION_PATH: &str = "/org/freedesktop/login1/session/auto";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+#[derive(Error, Debug)]
+pub enum LogindError {
+  #[error("Unknown logind action '{0}'")]
+  UnknownAction(String),
+  #[error("D-Bus error calling logind: {0}")]
+  DBusError(#[from] glib::Error),
+}
+
+/// Built-in button actions executed through org.freedesktop.login1 instead of a command.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogindAction {
+  Poweroff,
+  Reboot,
+  Suspend,
+  Hibernate,
+  HybridSleep,
+  Lock,
+  Logout,
+}
+
+impl LogindAction {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      LogindAction::Poweroff => "poweroff",
+      LogindAction::Reboot => "reboot",
+      LogindAction::Suspend => "suspend",
+      LogindAction::Hibernate => "hibernate",
+      LogindAction::HybridSleep => "hybrid-sleep",
+      LogindAction::Lock => "lock",
+      LogindAction::Logout => "logout",
+    }
+  }
+
+  fn method(&self) -> (&'static str, &'static str, &'static str) {
+    match self {
+      LogindAction::Poweroff => (MANAGER_PATH, MANAGER_INTERFACE, "PowerOff"),
+      LogindAction::Reboot => (MANAGER_PATH, MANAGER_INTERFACE, "Reboot"),
+      LogindAction::Suspend => (MANAGER_PATH, MANAGER_INTERFACE, "Suspend"),
+      LogindAction::Hibernate => (MANAGER_PATH, MANAGER_INTERFACE, "Hibernate"),
+      LogindAction::HybridSleep => (MANAGER_PATH, MANAGER_INTERFACE, "HybridSleep"),
+      LogindAction::Lock => (SESSION_PATH, SESSION_INTERFACE, "Lock"),
+      LogindAction::Logout => (SESSION_PATH, SESSION_INTERFACE, "Terminate"),
+    }
+  }
+
+  fn can_method(&self) -> Option<&'static str> {
+    match self {
+      LogindAction::Poweroff => Some("CanPowerOff"),
+      LogindAction::Reboot => Some("CanReboot"),
+      LogindAction::Suspend => Some("CanSuspend"),
+      LogindAction::Hibernate => Some("CanHibernate"),
+      LogindAction::HybridSleep => Some("CanHybridSleep"),
+      LogindAction::Lock | LogindAction::Logout => None,
+    }
+  }
+
+  /// The manager methods take an "interactive" flag allowing polkit to prompt for authentication.
+  fn parameters(&self) -> Option<Variant> {
+    match self {
+      LogindAction::Lock | LogindAction::Logout => None,
+      _ => Some((true,).into()),
+    }
+  }
+}
+
+impl fmt::Display for LogindAction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+impl FromStr for LogindAction {
+  type Err = LogindError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "poweroff" => Ok(LogindAction::Poweroff),
+      "reboot" => Ok(LogindAction::Reboot),
+      "suspend" => Ok(LogindAction::Suspend),
+      "hibernate" => Ok(LogindAction::Hibernate),
+      "hybrid-sleep" => Ok(LogindAction::HybridSleep),
+      "lock" => Ok(LogindAction::Lock),
+      "logout" => Ok(LogindAction::Logout),
+      _ => Err(LogindError::UnknownAction(s.to_string())),
+    }
+  }
+}
+
+pub async fn execute(action: LogindAction) -> Result<(), LogindError> {
+  let connection = gio::bus_get_future(BusType::System).await?;
+  execute_on(&connection, action).await
+}
+
+/// Runs an action through the logind on a given connection, the system bus outside of tests.
+pub async fn execute_on(connection: &DBusConnection, action: LogindAction) -> Result<(), LogindError> {
+  let (path, interface, method) = action.method();
+
+  connection.call_future(
+    Some(LOGIND_BUS_NAME),
+    path,
+    interface,
+    method,
+    action.parameters().as_ref(),
+    None,
+    DBusCallFlags::ALLOW_INTERACTIVE_AUTHORIZATION,
+    -1
+  ).await?;
+
+  Ok(())
+}
+
+/// Only a "yes" answer from logind counts, "challenge" would need an authentication prompt.
+fn is_yes(reply: Result<Variant, glib::Error>) -> bool {
+  match reply.ok().and_then(|reply| reply.get::<(String,)>()) {
+    Some((answer,)) => answer == "yes",
+    None => false,
+  }
+}
+
+pub async fn is_available(action: LogindAction) -> bool {
+  match gio::bus_get_future(BusType::System).await {
+    Ok(connection) => is_available_on(&connection, action).await,
+    Err(_) => action.can_method().is_none(),
+  }
+}
+
+/// Asks the logind on a given connection whether an action can run, actions without a Can* method always can.
+pub async fn is_available_on(connection: &DBusConnection, action: LogindAction) -> bool {
+  let Some(can_method) = action.can_method() else { return true };
+
+  let reply = connection.call_future(
+    Some(LOGIND_BUS_NAME),
+    MANAGER_PATH,
+    MANAGER_INTERFACE,
+    can_method,
+    None,
+    Some(VariantTy::new("(s)").unwrap()),
+    DBusCallFlags::NONE,
+    -1
+  ).await;
+
+  is_yes(reply)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use glib::prelude::*;
+  use gtk4::gio::{Cancellable, DBusConnectionFlags, DBusNodeInfo, TestDBus, TestDBusFlags};
+  use std::{cell::RefCell, future::Future, rc::Rc};
+
+  const MOCK_LOGIND_XML: &str = r#"<node>
+  <interface name="org.freedesktop.login1.Manager">
+    <method name="CanPowerOff"><arg type="s" direction="out"/></method>
+    <method name="CanReboot"><arg type="s" direction="out"/></method>
+    <method name="CanSuspend"><arg type="s" direction="out"/></method>
+    <method name="CanHibernate"><arg type="s" direction="out"/></method>
+    <method name="CanHybridSleep"><arg type="s" direction="out"/></method>
+    <method name="PowerOff"><arg type="b" direction="in"/></method>
+    <method name="Reboot"><arg type="b" direction="in"/></method>
+    <method name="Suspend"><arg type="b" direction="in"/></method>
+    <method name="Hibernate"><arg type="b" direction="in"/></method>
+    <method name="HybridSleep"><arg type="b" direction="in"/></method>
+  </interface>
+  <interface name="org.freedesktop.login1.Session">
+    <method name="Lock"/>
+    <method name="Terminate"/>
+  </interface>
+</node>"#;
+
+  const ALL_ACTIONS: [LogindAction; 7] = [
+    LogindAction::Poweroff,
+    LogindAction::Reboot,
+    LogindAction::Suspend,
+    LogindAction::Hibernate,
+    LogindAction::HybridSleep,
+    LogindAction::Lock,
+    LogindAction::Logout,
+  ];
+
+  /// Object path, method name and parameters of every action method the mock received.
+  type Calls = Rc<RefCell<Vec<(String, String, Variant)>>>;
+
+  fn connect(address: &str) -> DBusConnection {
+    let flags = DBusConnectionFlags::AUTHENTICATION_CLIENT | DBusConnectionFlags::MESSAGE_BUS_CONNECTION;
+    DBusConnection::for_address_sync(address, flags, None, None::<&Cancellable>).unwrap()
+  }
+
+  /// Serves org.freedesktop.login1 on the bus, answering each listed Can* method with its answer and failing the others.
+  fn serve_mock_logind(address: &str, answers: &[(&'static str, &'static str)]) -> (DBusConnection, Calls) {
+    let connection = connect(address);
+    let node = DBusNodeInfo::for_xml(MOCK_LOGIND_XML).unwrap();
+    let calls = Calls::default();
+
+    for (path, interface) in [(MANAGER_PATH, MANAGER_INTERFACE), (SESSION_PATH, SESSION_INTERFACE)] {
+      let calls = calls.clone();
+      let answers = answers.to_vec();
+
+      connection
+        .register_object(path, &node.lookup_interface(interface).unwrap())
+        .method_call(move |_, _, object_path, _, method, parameters, invocation| {
+          match answers.iter().find(|(can_method, _)| *can_method == method) {
+            Some((_, answer)) => invocation.return_value(Some(&(answer.to_string(),).to_variant())),
+            None if method.starts_with("Can") => {
+              invocation.return_dbus_error("org.freedesktop.DBus.Error.NotSupported", "No answer configured")
+            }
+            None => {
+              calls.borrow_mut().push((object_path.to_string(), method.to_string(), parameters));
+              invocation.return_value(None);
+            }
+          }
+        })
+        .build()
+        .unwrap();
+    }
+
+    connection.call_sync(
+      Some("org.freedesktop.DBus"),
+      "/org/freedesktop/DBus",
+      "org.freedesktop.DBus",
+      "RequestName",
+      Some(&(LOGIND_BUS_NAME, 0u32).to_variant()),
+      None,
+      DBusCallFlags::NONE,
+      -1,
+      None::<&Cancellable>
+    ).unwrap();
+
+    (connection, calls)
+  }
+
+  /// Runs a test against a mock logind on a private session bus, the D-Bus callbacks need a main context to run on.
+  fn with_mock_logind<F: Future>(answers: &[(&'static str, &'static str)], test: impl FnOnce(DBusConnection, Calls) -> F) -> F::Output {
+    let bus = TestDBus::new(TestDBusFlags::NONE);
+    bus.up();
+
+    let address = bus.bus_address().unwrap();
+    let context = glib::MainContext::new();
+
+    let output = context
+      .with_thread_default(|| {
+        let (_service, calls) = serve_mock_logind(&address, answers);
+        context.block_on(test(connect(&address), calls))
+      })
+      .unwrap();
+
+    bus.down();
+    output
+  }
+
+  #[test]
+  fn only_a_yes_answer_makes_an_action_available() {
+    let answers = [
+      ("CanPowerOff", "yes"),
+      ("CanReboot", "no"),
+      ("CanSuspend", "challenge"),
+      ("CanHibernate", "na"),
+      ("CanHybridSleep", "yes"),
+    ];
+
+    let available = with_mock_logind(&answers, |connection, _| async move {
+      let mut available = Vec::new();
+
+      for action in ALL_ACTIONS {
+        available.push(is_available_on(&connection, action).await);
+      }
+
+      available
+    });
+
+    assert_eq!(available, [true, false, false, false, true, true, true]);
+  }
+
+  #[test]
+  fn a_failed_can_call_makes_an_action_unavailable() {
+    let available = with_mock_logind(&[], |connection, _| async move {
+      is_available_on(&connection, LogindAction::Poweroff).await
+    });
+
+    assert!(!available);
+  }
+
+  #[test]
+  fn each_action_calls_its_logind_method() {
+    let calls = with_mock_logind(&[], |connection, calls| async move {
+      for action in ALL_ACTIONS {
+        execute_on(&connection, action).await.unwrap();
+      }
+
+      calls.take()
+    });
+
+    let interactive = (true,).to_variant();
+    let none = ().to_variant();
+
+    assert_eq!(calls, [
+      (MANAGER_PATH.to_string(), "PowerOff".to_string(), interactive.clone()),
+      (MANAGER_PATH.to_string(), "Reboot".to_string(), interactive.clone()),
+      (MANAGER_PATH.to_string(), "Suspend".to_string(), interactive.clone()),
+      (MANAGER_PATH.to_string(), "Hibernate".to_string(), interactive.clone()),
+      (MANAGER_PATH.to_string(), "HybridSleep".to_string(), interactive),
+      (SESSION_PATH.to_string(), "Lock".to_string(), none.clone()),
+      (SESSION_PATH.to_string(), "Terminate".to_string(), none),
+    ]);
+  }
+}
@@ -1,15 +1,52 @@
 use clap::Parser;
 use gtk4::prelude::*;
-use gtk4::{glib, Application};
+use gtk4::gio::{self, ApplicationCommandLine, ApplicationFlags};
+use gtk4::{glib, glib::translate::ToGlibPtr, Application};
+use std::ffi::CString;
 use curtains_close::options::Options;
 use curtains_close::css_provider;
-use curtains_close::application::app_main;
+use curtains_close::application::command_line_main;
+use curtains_close::settings::Settings;
+
+/// Writes to the stdout of the process that passed the command line, not necessarily this one.
+/// The safe *_literal bindings need GLib 2.80, so the message goes through a "%s" format instead.
+fn print(command_line: &ApplicationCommandLine, message: &str) {
+  let Ok(message) = CString::new(message) else { return };
+  unsafe { gio::ffi::g_application_command_line_print(command_line.to_glib_none().0, c"%s".as_ptr(), message.as_ptr()) };
+}
+
+/// Writes to the stderr of the process that passed the command line, not necessarily this one.
+fn printerr(command_line: &ApplicationCommandLine, message: &str) {
+  let Ok(message) = CString::new(message) else { return };
+  unsafe { gio::ffi::g_application_command_line_printerr(command_line.to_glib_none().0, c"%s".as_ptr(), message.as_ptr()) };
+}
 
 fn main() -> glib::ExitCode {
     let options = Options::parse();
 
+    // Validation does not touch GTK, so the check also runs without a display.
+    if options.check_config {
+      return match Settings::load_settings(&options) {
+        Ok(_) => {
+          println!("Settings are valid");
+          glib::ExitCode::SUCCESS
+        }
+        Err(e) => {
+          eprintln!("{}", e);
+          glib::ExitCode::FAILURE
+        }
+      };
+    }
+
+    // A --print menu belongs to the script that started it, so it never hands off to, or takes over, a running menu.
+    let flags = match options.print {
+      Some(_) => ApplicationFlags::HANDLES_COMMAND_LINE | ApplicationFlags::NON_UNIQUE,
+      None => ApplicationFlags::HANDLES_COMMAND_LINE,
+    };
+
     let app = Application::builder()
       .application_id("com.waltosoft.curtains-close")
+      .flags(flags)
       .build();
   
     let add_css_provider_options_clone = options.clone();
@@ -20,14 +57,42 @@ fn main() -> glib::ExitCode {
       }
     });
   
-    let app_main_options_clone = options.clone();
-    app.connect_activate(move |app| {
-      if let Err(e) = app_main(&app_main_options_clone, app) {
-        eprintln!("Error occurred running application: {:?}", e);
-        app.quit();
-      } 
+    // Runs in the first instance, later instances forward their arguments to it and exit with the returned code.
+    app.connect_command_line(|app, command_line| {
+      let mut options = match Options::try_parse_from(command_line.arguments()) {
+        Ok(options) => options,
+        Err(e) if e.use_stderr() => {
+          printerr(command_line, &e.to_string());
+          return e.exit_code();
+        }
+        Err(e) => {
+          print(command_line, &e.to_string());
+          return e.exit_code();
+        }
+      };
+
+      if let Some(cwd) = command_line.cwd() {
+        options.resolve_paths(&cwd);
+      }
+
+      if let Err(e) = command_line_main(&options, app) {
+        printerr(command_line, &format!("Error occurred running application: {}\n", e));
+
+        if app.windows().is_empty() {
+          app.quit();
+        }
+
+        return 1;
+      }
+
+      // In print mode choosing a button exits the process with status 0, so reaching the end of the run means the
+      // menu was closed without a selection and wrapper scripts can tell the two apart.
+      if options.print.is_some() {
+        return 1;
+      }
+
+      0
     });
   
-    app.run_with_args(&[] as &[&str])
-  
+    app.run()
 }
\ No newline at end of file
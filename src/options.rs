@@ -1,5 +1,7 @@
 use clap::{ArgAction, Parser};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::settings::{Anchor, PrintMode, WindowLayer};
 
 #[derive(Parser, Clone, Debug)]
 #[command(author, version, disable_version_flag = true, about, long_about = None)]
@@ -39,4 +41,77 @@ pub struct Options {
 
   #[arg(short = 'd', long = "delay-before-closing")]
   pub delay_before_closing: Option<u32>,
-}
\ No newline at end of file
+
+  #[arg(short = 'a', long = "default-action")]
+  pub default_action: Option<String>,
+
+  #[arg(short = 't', long = "timeout-seconds")]
+  pub timeout_seconds: Option<u32>,
+
+  #[arg(short = 'f', long = "initial-focus")]
+  pub initial_focus: Option<String>,
+
+  #[arg(short = 'k', long = "show-keybinds")]
+  pub show_keybinds: bool,
+
+  #[arg(short = 'm', long = "monitor-mode")]
+  pub monitor_mode: Option<String>,
+
+  #[arg(short = 'o', long = "output")]
+  pub output: Option<String>,
+
+  #[arg(short = 'B', long = "dismiss-on-backdrop-click")]
+  pub dismiss_on_backdrop_click: Option<bool>,
+
+  #[arg(short = 'K', long = "dismiss-keys", value_delimiter = ',')]
+  pub dismiss_keys: Option<Vec<String>>,
+
+  #[arg(short = 'N', long = "no-dismiss")]
+  pub no_dismiss: bool,
+
+  #[arg(short = 'Y', long = "layer", value_enum)]
+  pub layer: Option<WindowLayer>,
+
+  #[arg(short = 'A', long = "anchor", value_enum, value_delimiter = ',')]
+  pub anchor: Option<Vec<Anchor>>,
+
+  #[arg(short = 'W', long = "width")]
+  pub width: Option<i32>,
+
+  #[arg(short = 'H', long = "height")]
+  pub height: Option<i32>,
+
+  #[arg(short = 'T', long = "no-layer-shell")]
+  pub no_layer_shell: bool,
+
+  #[arg(short = 'I', long = "icon-size")]
+  pub icon_size: Option<i32>,
+
+  #[arg(long = "toggle", conflicts_with_all = ["open", "close"])]
+  pub toggle: bool,
+
+  #[arg(long = "open", conflicts_with = "close")]
+  pub open: bool,
+
+  #[arg(long = "close")]
+  pub close: bool,
+
+  #[arg(short = 'p', long = "print", visible_alias = "dry-run", value_enum, num_args = 0..=1, default_missing_value = "id")]
+  pub print: Option<PrintMode>,
+
+  #[arg(long = "check-config")]
+  pub check_config: bool,
+}
+impl Options {
+  /// Relative paths are resolved against the working directory of the command line that passed them, which can be a
+  /// later invocation forwarding its arguments to the running instance.
+  pub fn resolve_paths(&mut self, cwd: &Path) {
+    let paths = [&mut self.css_path, &mut self.css_loader_path, &mut self.settings_path, &mut self.settings_loader_path];
+
+    for path in paths.into_iter().flatten() {
+      if path.is_relative() {
+        *path = cwd.join(&path);
+      }
+    }
+  }
+}
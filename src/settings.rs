@@ -1,16 +1,34 @@
 use dirs;
-use std::{fs, io, path::Path};
+use gtk4::gdk::{Key, ModifierType};
+use std::{collections::{HashMap, HashSet}, fmt, fs, io, path::Path};
 use serde::Deserialize;
 use thiserror::Error;
 
-use crate::{content_loader::{load_content_from_script, ContentLoaderError}, options::Options};
+use crate::{content_loader::{load_content_from_script, ContentLoaderError}, json_path, keybind, logind::LogindAction, options::Options};
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize)]
 struct RawSettings {
   pub buttons_per_row: Option<u32>,
   pub column_spacing: Option<u32>,
   pub row_spacing: Option<u32>,
   pub delay_before_closing: Option<u32>,
+  pub default_action: Option<String>,
+  pub timeout_seconds: Option<u32>,
+  pub initial_focus: Option<String>,
+  pub show_keybinds: Option<bool>,
+  pub monitor_mode: Option<MonitorMode>,
+  pub output: Option<String>,
+  pub dismiss_on_backdrop_click: Option<bool>,
+  pub dismiss_keys: Option<Vec<String>>,
+  pub no_dismiss: Option<bool>,
+  pub layer: Option<WindowLayer>,
+  pub anchor: Option<Vec<Anchor>>,
+  pub margin: Option<Margins>,
+  pub width: Option<i32>,
+  pub height: Option<i32>,
+  pub no_layer_shell: Option<bool>,
+  pub shell: Option<Vec<String>>,
+  pub icon_size: Option<i32>,
   pub buttons: Option<Vec<ButtonInfo>>
 }
 
@@ -20,96 +38,1065 @@ pub struct Settings {
   pub column_spacing: u32,
   pub row_spacing: u32,
   pub delay_before_closing: u32,
+  pub default_action: Option<String>,
+  pub timeout_seconds: u32,
+  pub initial_focus: Option<String>,
+  pub show_keybinds: bool,
+  pub monitor_mode: MonitorMode,
+  pub output: Option<String>,
+  pub dismiss_on_backdrop_click: bool,
+  pub dismiss_keys: Vec<String>,
+  pub no_dismiss: bool,
+  pub layer: WindowLayer,
+  pub anchor: Vec<Anchor>,
+  pub margin: Margins,
+  pub width: Option<i32>,
+  pub height: Option<i32>,
+  pub no_layer_shell: bool,
+  pub shell: Vec<String>,
+  pub icon_size: i32,
+  pub print: Option<PrintMode>,
   pub buttons: Vec<ButtonInfo>
 }
 
+/// Which monitors get the button grid, the remaining monitors only get the dimmed backdrop.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub enum MonitorMode {
+  Focused,
+  All,
+  Output(String),
+}
+
+impl From<String> for MonitorMode {
+  fn from(value: String) -> Self {
+    match value.as_str() {
+      "focused" => MonitorMode::Focused,
+      "all" => MonitorMode::All,
+      _ => MonitorMode::Output(value),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowLayer {
+  Top,
+  Overlay,
+}
+
+/// What --print writes to stdout for the activated button instead of running it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum PrintMode {
+  Id,
+  Command,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Anchor {
+  Top,
+  Right,
+  Bottom,
+  Left,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct Margins {
+  #[serde(default)]
+  pub top: i32,
+  #[serde(default)]
+  pub right: i32,
+  #[serde(default)]
+  pub bottom: i32,
+  #[serde(default)]
+  pub left: i32,
+}
+
+/// A button command is either a string run through the configured shell, or an argv array run directly.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ButtonCommand {
+  Shell(String),
+  Argv(Vec<String>),
+}
+
+impl ButtonCommand {
+  pub fn argv(&self, shell: &[String]) -> Vec<String> {
+    match self {
+      ButtonCommand::Shell(command) => {
+        let mut argv = shell.to_vec();
+        argv.push(command.clone());
+        argv
+      }
+      ButtonCommand::Argv(argv) => argv.clone(),
+    }
+  }
+
+  /// The command as written in the settings, used by --print.
+  pub fn display(&self) -> String {
+    match self {
+      ButtonCommand::Shell(command) => command.clone(),
+      ButtonCommand::Argv(argv) => argv.join(" "),
+    }
+  }
+
+  fn is_empty(&self) -> bool {
+    match self {
+      ButtonCommand::Shell(command) => command.trim().is_empty(),
+      ButtonCommand::Argv(argv) => argv.first().is_none_or(|program| program.is_empty()),
+    }
+  }
+}
+
+/// Decides whether a button is shown, either by the exit status of a probe command or by asking logind about an action.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ButtonCondition {
+  #[serde(default)]
+  pub command: Option<ButtonCommand>,
+  #[serde(default)]
+  pub action: Option<LogindAction>,
+  #[serde(default)]
+  pub hide: bool,
+}
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct ButtonInfo {
   pub id: String,
-  pub command: String,
+  #[serde(default)]
+  pub command: Option<ButtonCommand>,
+  #[serde(default)]
+  pub action: Option<LogindAction>,
   pub icon: String,
   pub text: String,
-  pub keybind: char,
+  #[serde(default)]
+  pub tooltip: Option<String>,
+  #[serde(default)]
+  pub description: Option<String>,
+  #[serde(default, deserialize_with = "keybind::deserialize_keybinds")]
+  pub keybind: Vec<String>,
+  #[serde(default)]
+  pub confirm: bool,
+  #[serde(default)]
+  pub available_if: Option<ButtonCondition>,
+  #[serde(default)]
+  pub children: Option<Vec<ButtonInfo>>,
+  #[serde(default)]
+  pub row: Option<u32>,
+  #[serde(default)]
+  pub column: Option<u32>,
+  #[serde(default)]
+  pub width: Option<u32>,
+  #[serde(default)]
+  pub height: Option<u32>,
+}
+
+/// Largest number of columns or rows a page can have, keeps placements well within what GTK can attach.
+const MAX_GRID_SIZE: u32 = 100;
+
+/// Grid cell and span a button is attached at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+  pub column: u32,
+  pub row: u32,
+  pub width: u32,
+  pub height: u32,
+}
+
+impl Placement {
+  fn cells(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+    (self.row..self.row + self.height).flat_map(move |row| (self.column..self.column + self.width).map(move |column| (column, row)))
+  }
 }
 
 #[derive(Error, Debug)]
 pub enum LoadSettingsError {
-    #[error("IO error")]
+    #[error("IO error: {0}")]
     IoError(#[from] io::Error),
-    #[error("JSON error")]
+    #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
+    #[error("Invalid settings:{}", format_problems(.0))]
+    InvalidSettings(Vec<SettingsProblem>),
+    #[error("{0}")]
+    InvalidJson(String),
+    #[error("Button id '{0}' is used by more than one button")]
+    DuplicateId(String),
     #[error("Settings content loader error")]
     ContentLoaderError(#[from] ContentLoaderError),
     #[error("No content or path")]
-    ContentOrPathNotFound
+    ContentOrPathNotFound,
+    #[error("Default action '{0}' does not match the id of any button")]
+    DefaultActionNotFound(String),
+    #[error("Initial focus '{0}' does not match the id of any button")]
+    InitialFocusNotFound(String),
+    #[error("Button '{0}' has an empty command")]
+    EmptyCommand(String),
+    #[error("Button '{0}' must have exactly one of command, action or children")]
+    CommandOrActionRequired(String),
+    #[error("Keybind '{0}' of button '{1}' is not a valid accelerator")]
+    InvalidKeybind(String, String),
+    #[error("Keybind '{0}' of button '{1}' is already used by button '{2}'")]
+    DuplicateKeybind(String, String, String),
+    #[error("Dismiss key '{0}' is not a valid accelerator")]
+    InvalidDismissKey(String),
+    #[error("Dismiss key '{0}' is also the keybind of button '{1}'")]
+    DismissKeyConflict(String, String),
+    #[error("The available_if condition of button '{0}' must have exactly one non empty command or action")]
+    InvalidCondition(String),
+    #[error("Shell must contain at least a program")]
+    EmptyShell,
+    #[error("Button '{0}' must set both row and column, or neither")]
+    IncompletePlacement(String),
+    #[error("Button '{0}' must have a width and height of at least 1")]
+    InvalidSpan(String),
+    #[error("Button '{0}' does not fit in a grid of {1} columns and {max} rows", max = MAX_GRID_SIZE)]
+    PlacementOutOfRange(String, u32),
+    #[error("buttons_per_row must be at most {max}, got {0}", max = MAX_GRID_SIZE)]
+    InvalidButtonsPerRow(u32),
+    #[error("Button '{0}' overlaps button '{1}'")]
+    OverlappingButtons(String, String)
+}
+
+/// One problem found in the settings, with the path of the offending field and where it is in the JSON when known.
+#[derive(Debug)]
+pub struct SettingsProblem {
+  pub source: Option<String>,
+  pub path: String,
+  pub location: Option<(usize, usize)>,
+  pub error: LoadSettingsError,
+}
+
+impl fmt::Display for SettingsProblem {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(source) = &self.source {
+      write!(f, "{}:", source)?;
+    }
+
+    if let Some((line, column)) = self.location {
+      write!(f, "{}:{}:", line, column)?;
+    }
+
+    if self.source.is_some() || self.location.is_some() {
+      write!(f, " ")?;
+    }
+
+    if !self.path.is_empty() {
+      write!(f, "{}: ", self.path)?;
+    }
+
+    write!(f, "{}", self.error)
+  }
+}
+
+fn format_problems(problems: &[SettingsProblem]) -> String {
+  problems.iter().map(|problem| format!("\n  {}", problem)).collect()
+}
+
+/// Settings given on the command line are not in the JSON, so problems with them have no location.
+fn overridden_by_options(problem: &SettingsProblem, options: &Options) -> bool {
+  let field = problem.path.split(['.', '[']).next().unwrap_or_default();
+
+  match field {
+    // Whether a button fits depends on --buttons-per-row, the buttons themselves are located through their layer.
+    "buttons" => options.buttons_per_row.is_some() && matches!(problem.error, LoadSettingsError::PlacementOutOfRange(..)),
+    "buttons_per_row" => options.buttons_per_row.is_some(),
+    "default_action" => options.default_action.is_some(),
+    "initial_focus" => options.initial_focus.is_some(),
+    "dismiss_keys" => options.dismiss_keys.is_some() || options.no_dismiss,
+    _ => false,
+  }
+}
+
+/// One settings source, the layers are merged in the order they are read with later layers overriding earlier ones.
+struct Layer {
+  source: String,
+  json: String,
+  raw_settings: RawSettings,
+}
+
+impl Layer {
+  fn parse(source: String, json: String, problems: &mut Vec<SettingsProblem>) -> Option<Layer> {
+    match serde_json::from_str(&json) {
+      Ok(raw_settings) => Some(Layer { source, json, raw_settings }),
+      Err(e) => {
+        let message = e.to_string();
+        let message = message.rsplit_once(" at line ").map_or(message.as_str(), |(message, _)| message).to_string();
+
+        problems.push(SettingsProblem {
+          path: json_path::path_at(&json, e.line(), e.column()).unwrap_or_default(),
+          source: Some(source),
+          location: Some((e.line(), e.column())),
+          error: LoadSettingsError::InvalidJson(message),
+        });
+
+        None
+      }
+    }
+  }
+}
+
+/// Reads every layer that exists: /etc, then /usr/local/etc, then the user's config, then the files and content given
+/// on the command line.  Parse errors in every layer are reported together.
+fn read_layers(options: &Options) -> Result<Vec<Layer>, LoadSettingsError> {
+  let mut sources: Vec<(String, String)> = Vec::new();
+
+  for path in [Path::new("/etc/curtains-close/settings.json"), Path::new("/usr/local/etc/curtains-close/settings.json")] {
+    if path.exists() {
+      sources.push((path.display().to_string(), fs::read_to_string(path)?));
+    }
+  }
+
+  if let Some(home_dir) = dirs::home_dir() {
+    let curtains_config_loaderpath = home_dir.join(".config/curtains/close/settings.sh");
+    let curtains_config_path = home_dir.join(".config/curtains/close/settings.json");
+
+    if curtains_config_loaderpath.exists() {
+      sources.push((curtains_config_loaderpath.display().to_string(), load_content_from_script(&curtains_config_loaderpath)?));
+    } else if curtains_config_path.exists() {
+      sources.push((curtains_config_path.display().to_string(), fs::read_to_string(&curtains_config_path)?));
+    }
+  }
+
+  if let Some(path) = &options.settings_path {
+    sources.push((path.display().to_string(), fs::read_to_string(path)?));
+  }
+
+  if let Some(settings_loader_path) = &options.settings_loader_path {
+    sources.push((settings_loader_path.display().to_string(), load_content_from_script(settings_loader_path)?));
+  }
+
+  if let Some(content) = &options.settings_content {
+    sources.push(("--settings-content".to_string(), content.clone()));
+  }
+
+  let mut problems = Vec::new();
+  let layers: Vec<Layer> = sources
+    .into_iter()
+    .filter_map(|(source, json)| Layer::parse(source, json, &mut problems))
+    .collect();
+
+  if !problems.is_empty() {
+    return Err(LoadSettingsError::InvalidSettings(problems));
+  }
+
+  Ok(layers)
+}
+
+/// Where each merged button came from, as the index of its layer and its index in that layer's buttons.
+type ButtonOrigins = Vec<(usize, usize)>;
+
+/// Merges the layers field by field.  Buttons are merged by id, a button replaces the one with the same id from an
+/// earlier layer and keeps its position, new buttons are added after the existing ones.
+fn merge_layers(layers: &[Layer]) -> (RawSettings, ButtonOrigins) {
+  let mut merged = RawSettings::default();
+  let mut buttons: Vec<ButtonInfo> = Vec::new();
+  let mut origins = ButtonOrigins::new();
+
+  for (layer_index, layer) in layers.iter().enumerate() {
+    let raw = layer.raw_settings.clone();
+
+    merge_buttons(&mut buttons, &mut origins, raw.buttons.as_deref().unwrap_or_default(), layer_index);
+
+    merged = RawSettings {
+      buttons_per_row: raw.buttons_per_row.or(merged.buttons_per_row),
+      column_spacing: raw.column_spacing.or(merged.column_spacing),
+      row_spacing: raw.row_spacing.or(merged.row_spacing),
+      delay_before_closing: raw.delay_before_closing.or(merged.delay_before_closing),
+      default_action: raw.default_action.or(merged.default_action),
+      timeout_seconds: raw.timeout_seconds.or(merged.timeout_seconds),
+      initial_focus: raw.initial_focus.or(merged.initial_focus),
+      show_keybinds: raw.show_keybinds.or(merged.show_keybinds),
+      monitor_mode: raw.monitor_mode.or(merged.monitor_mode),
+      output: raw.output.or(merged.output),
+      dismiss_on_backdrop_click: raw.dismiss_on_backdrop_click.or(merged.dismiss_on_backdrop_click),
+      dismiss_keys: raw.dismiss_keys.or(merged.dismiss_keys),
+      no_dismiss: raw.no_dismiss.or(merged.no_dismiss),
+      layer: raw.layer.or(merged.layer),
+      anchor: raw.anchor.or(merged.anchor),
+      margin: raw.margin.or(merged.margin),
+      width: raw.width.or(merged.width),
+      height: raw.height.or(merged.height),
+      no_layer_shell: raw.no_layer_shell.or(merged.no_layer_shell),
+      shell: raw.shell.or(merged.shell),
+      icon_size: raw.icon_size.or(merged.icon_size),
+      buttons: None,
+    };
+  }
+
+  if !layers.is_empty() {
+    merged.buttons = Some(buttons);
+  }
+
+  (merged, origins)
+}
+
+/// Replaces the buttons of earlier layers that share an id with one of the layer's buttons, keeping their position, and
+/// adds the rest after them.
+fn merge_buttons(buttons: &mut Vec<ButtonInfo>, origins: &mut ButtonOrigins, layer_buttons: &[ButtonInfo], layer_index: usize) {
+  for (index, button) in layer_buttons.iter().enumerate() {
+    // Only buttons from earlier layers are replaced, duplicate ids within one layer are kept for validation to report.
+    let replaced = buttons
+      .iter()
+      .zip(origins.iter())
+      .position(|(existing, (origin_layer, _))| *origin_layer < layer_index && existing.id == button.id);
+
+    match replaced {
+      Some(position) => {
+        buttons[position] = button.clone();
+        origins[position] = (layer_index, index);
+      }
+      None => {
+        buttons.push(button.clone());
+        origins.push((layer_index, index));
+      }
+    }
+  }
+}
+
+/// Finds the layer and position a problem's path refers to.  Button paths are translated back through the merge,
+/// other fields come from the last layer that sets them.
+fn locate_problem(problem: &mut SettingsProblem, layers: &[Layer], origins: &ButtonOrigins) {
+  if let Some(rest) = problem.path.strip_prefix("buttons[") {
+    let Some((index, rest)) = rest.split_once(']') else { return };
+    let Some(&(layer_index, original_index)) = index.parse::<usize>().ok().and_then(|index| origins.get(index)) else { return };
+    // Buttons from --buttons come after the last layer and are not in any file.
+    let Some(layer) = layers.get(layer_index) else { return };
+
+    problem.source = Some(layer.source.clone());
+    problem.location = json_path::locate(&layer.json, &format!("buttons[{}]{}", original_index, rest));
+    return;
+  }
+
+  let found = layers
+    .iter()
+    .rev()
+    .find_map(|layer| json_path::locate(&layer.json, &problem.path).map(|location| (layer, location)));
+
+  if let Some((layer, location)) = found {
+    problem.source = Some(layer.source.clone());
+    problem.location = Some(location);
+  }
+}
+
+fn override_settings(raw_settings: RawSettings, options: &Options) -> Settings {
+  let no_dismiss = options.no_dismiss || raw_settings.no_dismiss.unwrap_or(false);
+
+  Settings {
+    buttons_per_row: options.buttons_per_row.unwrap_or_else(|| raw_settings.buttons_per_row.unwrap_or(3)),
+    column_spacing: options.column_spacing.unwrap_or_else(|| raw_settings.column_spacing.unwrap_or(5)),
+    row_spacing: options.row_spacing.unwrap_or_else(|| raw_settings.row_spacing.unwrap_or(5)),
+    delay_before_closing: options.delay_before_closing.unwrap_or_else(|| raw_settings.delay_before_closing.unwrap_or(0)),
+    default_action: options.default_action.clone().or(raw_settings.default_action),
+    timeout_seconds: options.timeout_seconds.unwrap_or_else(|| raw_settings.timeout_seconds.unwrap_or(0)),
+    initial_focus: options.initial_focus.clone().or(raw_settings.initial_focus),
+    show_keybinds: options.show_keybinds || raw_settings.show_keybinds.unwrap_or(false),
+    monitor_mode: options.monitor_mode.clone().map(MonitorMode::from).or(raw_settings.monitor_mode).unwrap_or(MonitorMode::Focused),
+    output: options.output.clone().or(raw_settings.output),
+    dismiss_on_backdrop_click: !no_dismiss && options.dismiss_on_backdrop_click.unwrap_or_else(|| raw_settings.dismiss_on_backdrop_click.unwrap_or(true)),
+    dismiss_keys: if no_dismiss {
+      Vec::new()
+    } else {
+      options.dismiss_keys.clone().unwrap_or_else(|| raw_settings.dismiss_keys.unwrap_or_else(|| vec!["Escape".to_string()]))
+    },
+    no_dismiss,
+    layer: options.layer.or(raw_settings.layer).unwrap_or(WindowLayer::Overlay),
+    anchor: options.anchor.clone().or(raw_settings.anchor).unwrap_or_else(|| vec![Anchor::Top, Anchor::Right, Anchor::Bottom, Anchor::Left]),
+    margin: raw_settings.margin.unwrap_or_default(),
+    width: options.width.or(raw_settings.width),
+    height: options.height.or(raw_settings.height),
+    no_layer_shell: options.no_layer_shell || raw_settings.no_layer_shell.unwrap_or(false),
+    shell: raw_settings.shell.unwrap_or_else(|| vec!["sh".to_string(), "-c".to_string()]),
+    icon_size: options.icon_size.unwrap_or_else(|| raw_settings.icon_size.unwrap_or(48)),
+    print: options.print,
+    buttons: raw_settings.buttons.unwrap_or_else(Vec::new)
+  }
+}
+
+fn problem(path: String, error: LoadSettingsError) -> SettingsProblem {
+  SettingsProblem { source: None, path, location: None, error }
+}
+
+/// Checks everything deserialization cannot, collecting every problem rather than stopping at the first.
+fn validate(settings: &Settings) -> Vec<SettingsProblem> {
+  let mut problems = Vec::new();
+
+  if settings.shell.first().is_none_or(|program| program.is_empty()) {
+    problems.push(problem("shell".to_string(), LoadSettingsError::EmptyShell));
+  }
+
+  if settings.buttons_per_row > MAX_GRID_SIZE {
+    problems.push(problem("buttons_per_row".to_string(), LoadSettingsError::InvalidButtonsPerRow(settings.buttons_per_row)));
+  }
+
+  validate_buttons(&settings.buttons, "buttons", settings.buttons_per_row, &mut problems);
+  validate_ids(&settings.buttons, "buttons", &mut HashSet::new(), &mut problems);
+  validate_keybinds(settings, &mut problems);
+
+  if let Some(default_action) = &settings.default_action {
+    if !settings.buttons.iter().any(|button| &button.id == default_action) {
+      problems.push(problem("default_action".to_string(), LoadSettingsError::DefaultActionNotFound(default_action.clone())));
+    }
+  }
+
+  if let Some(initial_focus) = &settings.initial_focus {
+    if !settings.buttons.iter().any(|button| &button.id == initial_focus) {
+      problems.push(problem("initial_focus".to_string(), LoadSettingsError::InitialFocusNotFound(initial_focus.clone())));
+    }
+  }
+
+  problems
+}
+
+fn validate_buttons(buttons: &[ButtonInfo], path: &str, buttons_per_row: u32, problems: &mut Vec<SettingsProblem>) {
+  let (_, placement_errors) = layout_buttons(buttons.iter(), buttons_per_row);
+
+  for (index, error) in placement_errors {
+    problems.push(problem(format!("{}[{}]", path, index), error));
+  }
+
+  for (index, button) in buttons.iter().enumerate() {
+    let button_path = format!("{}[{}]", path, index);
+
+    match (&button.command, &button.action, &button.children) {
+      (Some(command), None, None) if command.is_empty() => {
+        problems.push(problem(format!("{}.command", button_path), LoadSettingsError::EmptyCommand(button.id.clone())));
+      }
+      (Some(_), None, None) | (None, Some(_), None) => {},
+      (None, None, Some(children)) => validate_buttons(children, &format!("{}.children", button_path), buttons_per_row, problems),
+      _ => problems.push(problem(button_path.clone(), LoadSettingsError::CommandOrActionRequired(button.id.clone()))),
+    }
+
+    if let Some(condition) = &button.available_if {
+      match (&condition.command, &condition.action) {
+        (Some(command), None) if !command.is_empty() => {},
+        (None, Some(_)) => {},
+        _ => problems.push(problem(format!("{}.available_if", button_path), LoadSettingsError::InvalidCondition(button.id.clone()))),
+      }
+    }
+  }
+}
+
+/// Ids name buttons for CSS, default_action and the D-Bus actions, so they must be unique across every page.
+fn validate_ids<'a>(buttons: &'a [ButtonInfo], path: &str, seen: &mut HashSet<&'a str>, problems: &mut Vec<SettingsProblem>) {
+  for (index, button) in buttons.iter().enumerate() {
+    let button_path = format!("{}[{}]", path, index);
+
+    if !seen.insert(&button.id) {
+      problems.push(problem(format!("{}.id", button_path), LoadSettingsError::DuplicateId(button.id.clone())));
+    }
+
+    if let Some(children) = &button.children {
+      validate_ids(children, &format!("{}.children", button_path), seen, problems);
+    }
+  }
 }
 
-fn from_json(json_str: &str, options: &Options) -> Result<Settings, LoadSettingsError> {
-  let raw_settings = serde_json::from_str(json_str).map_err(LoadSettingsError::from)?;
-  let settings = override_settings(raw_settings, options)?;
-  return Ok(settings);
+/// Buttons with a row and column are placed first, the rest flow left to right into the free cells after the previous
+/// flowing button, wrapping after buttons_per_row columns.
+pub fn place_buttons<'a>(buttons: impl Iterator<Item = &'a ButtonInfo>, buttons_per_row: u32) -> Result<Vec<Placement>, LoadSettingsError> {
+  let (placements, errors) = layout_buttons(buttons, buttons_per_row);
+
+  match errors.into_iter().next() {
+    Some((_, error)) => Err(error),
+    None => Ok(placements.into_iter().flatten().collect()),
+  }
+}
+
+/// Places every button it can and returns the index and error of each button it could not place.
+fn layout_buttons<'a>(buttons: impl Iterator<Item = &'a ButtonInfo>, buttons_per_row: u32) -> (Vec<Option<Placement>>, Vec<(usize, LoadSettingsError)>) {
+  let buttons_per_row = buttons_per_row.clamp(1, MAX_GRID_SIZE);
+  let buttons: Vec<&ButtonInfo> = buttons.collect();
+  let mut placements: Vec<Option<Placement>> = vec![None; buttons.len()];
+  let mut flowing = vec![false; buttons.len()];
+  let mut errors = Vec::new();
+  let mut occupied: HashMap<(u32, u32), &str> = HashMap::new();
+
+  for (index, button) in buttons.iter().enumerate() {
+    let width = button.width.unwrap_or(1);
+    let height = button.height.unwrap_or(1);
+
+    if width == 0 || height == 0 {
+      errors.push((index, LoadSettingsError::InvalidSpan(button.id.clone())));
+      continue;
+    }
+
+    if width > buttons_per_row || height > MAX_GRID_SIZE {
+      errors.push((index, LoadSettingsError::PlacementOutOfRange(button.id.clone(), buttons_per_row)));
+      continue;
+    }
+
+    let (column, row) = match (button.column, button.row) {
+      (Some(column), Some(row)) => (column, row),
+      (None, None) => {
+        flowing[index] = true;
+        continue;
+      }
+      _ => {
+        errors.push((index, LoadSettingsError::IncompletePlacement(button.id.clone())));
+        continue;
+      }
+    };
+
+    let fits = column.checked_add(width).is_some_and(|end| end <= buttons_per_row)
+      && row.checked_add(height).is_some_and(|end| end <= MAX_GRID_SIZE);
+
+    if !fits {
+      errors.push((index, LoadSettingsError::PlacementOutOfRange(button.id.clone(), buttons_per_row)));
+      continue;
+    }
+
+    let placement = Placement { column, row, width, height };
+
+    if let Some(other) = placement.cells().find_map(|cell| occupied.get(&cell)) {
+      errors.push((index, LoadSettingsError::OverlappingButtons(button.id.clone(), other.to_string())));
+      continue;
+    }
+
+    for cell in placement.cells() {
+      occupied.insert(cell, &button.id);
+    }
+
+    placements[index] = Some(placement);
+  }
+
+  let (mut column, mut row) = (0, 0);
+
+  for (index, button) in buttons.iter().enumerate() {
+    if !flowing[index] {
+      continue;
+    }
+
+    let width = button.width.unwrap_or(1);
+    let height = button.height.unwrap_or(1);
+
+    loop {
+      if column + width > buttons_per_row {
+        column = 0;
+        row += 1;
+        continue;
+      }
+
+      let placement = Placement { column, row, width, height };
+
+      if placement.cells().any(|cell| occupied.contains_key(&cell)) {
+        column += 1;
+        continue;
+      }
+
+      for cell in placement.cells() {
+        occupied.insert(cell, &button.id);
+      }
+
+      placements[index] = Some(placement);
+      column += width;
+      break;
+    }
+  }
+
+  (placements, errors)
 }
 
-fn from_file(file_path: &Path, options: &Options) -> Result<Settings, LoadSettingsError> {
-  let data = fs::read_to_string(file_path)?;
-  return Ok(from_json(&data, &options)?);
+fn validate_keybinds(settings: &Settings, problems: &mut Vec<SettingsProblem>) {
+  let mut dismiss_keys = HashMap::new();
+
+  for (index, accelerator) in settings.dismiss_keys.iter().enumerate() {
+    match keybind::parse(accelerator) {
+      Some(parsed) => { dismiss_keys.insert(parsed, accelerator.clone()); },
+      None => problems.push(problem(format!("dismiss_keys[{}]", index), LoadSettingsError::InvalidDismissKey(accelerator.clone()))),
+    }
+  }
+
+  validate_page_keybinds(&settings.buttons, "buttons", &dismiss_keys, problems);
 }
 
-fn override_settings(raw_settings: RawSettings, options: &Options) -> Result<Settings, LoadSettingsError> {
-  let mut button_info: Option<Vec<ButtonInfo>> = None;
-  
-  if let Some(raw_buttons) = &options.buttons {
-    button_info = Some(serde_json::from_str::<Vec<ButtonInfo>>(raw_buttons.as_str()).map_err(LoadSettingsError::from)?);
-  } 
+/// Keybinds only apply to the page that is showing, so they only need to be unique within a page.
+fn validate_page_keybinds(
+  buttons: &[ButtonInfo],
+  path: &str,
+  dismiss_keys: &HashMap<(Key, ModifierType), String>,
+  problems: &mut Vec<SettingsProblem>
+) {
+  let mut seen = HashMap::new();
+
+  for (index, button) in buttons.iter().enumerate() {
+    let button_path = format!("{}[{}]", path, index);
 
-  Ok(
-    Settings {
-      buttons_per_row: options.buttons_per_row.unwrap_or_else(|| raw_settings.buttons_per_row.unwrap_or(3)),
-      column_spacing: options.column_spacing.unwrap_or_else(|| raw_settings.column_spacing.unwrap_or(5)),
-      row_spacing: options.row_spacing.unwrap_or_else(|| raw_settings.row_spacing.unwrap_or(5)),
-      delay_before_closing: options.delay_before_closing.unwrap_or_else(|| raw_settings.delay_before_closing.unwrap_or(0)),
-      buttons: button_info.unwrap_or_else(|| raw_settings.buttons.unwrap_or_else(Vec::new))
+    if let Some(children) = &button.children {
+      validate_page_keybinds(children, &format!("{}.children", button_path), dismiss_keys, problems);
     }
-  )
+
+    for (keybind_index, accelerator) in button.keybind.iter().enumerate() {
+      // A single keybind may be written as a plain string, which has no index in the JSON.
+      let keybind_path = if button.keybind.len() == 1 {
+        format!("{}.keybind", button_path)
+      } else {
+        format!("{}.keybind[{}]", button_path, keybind_index)
+      };
+
+      let Some(parsed) = keybind::parse(accelerator) else {
+        problems.push(problem(keybind_path, LoadSettingsError::InvalidKeybind(accelerator.clone(), button.id.clone())));
+        continue;
+      };
+
+      if let Some(dismiss_key) = dismiss_keys.get(&parsed) {
+        problems.push(problem(keybind_path, LoadSettingsError::DismissKeyConflict(dismiss_key.clone(), button.id.clone())));
+        continue;
+      }
+
+      if let Some(other_id) = seen.insert(parsed, button.id.clone()) {
+        problems.push(problem(keybind_path, LoadSettingsError::DuplicateKeybind(accelerator.clone(), button.id.clone(), other_id)));
+      }
+    }
+  }
 }
 
 impl Settings {
   pub fn load_settings(options: &Options) -> Result<Settings, LoadSettingsError> {
-    if let Some(content) = &options.settings_content {
-      return from_json(&content, &options);
-    } 
-    else if let Some(settings_loader_path) = &options.settings_loader_path {
-      let settings_content = load_content_from_script(&settings_loader_path)?;
-      return from_json(&settings_content, &options);
-    } 
-    else if let Some(path) = &options.settings_path {
-      return from_file(&path, &options);
-    }
-
-    if let Some(home_dir) = dirs::home_dir() {
-      let curtains_config_loaderpath = home_dir.join(".config/curtains/close/settings.sh");
-      if curtains_config_loaderpath.exists() {
-        let settings_content = load_content_from_script(&curtains_config_loaderpath)?;
-        return from_json(&settings_content, &options);
-      }
+    let layers = read_layers(options)?;
+
+    if layers.is_empty() && options.buttons.is_none() {
+      return Err(LoadSettingsError::ContentOrPathNotFound);
+    }
+
+    Settings::from_layers(&layers, options)
+  }
+
+  /// Merges the layers, applies the command line on top and validates the result, locating each problem in its layer.
+  fn from_layers(layers: &[Layer], options: &Options) -> Result<Settings, LoadSettingsError> {
+    let (mut raw_settings, mut origins) = merge_layers(layers);
 
-      let curtains_config_path = home_dir.join(".config/curtains/close/settings.json");
-      if curtains_config_path.exists() {
-        return from_file(&curtains_config_path, &options);
+    // --buttons is merged by id like one more layer on top of the others.
+    if let Some(raw_buttons) = &options.buttons {
+      let buttons = serde_json::from_str::<Vec<ButtonInfo>>(raw_buttons)?;
+      merge_buttons(raw_settings.buttons.get_or_insert_with(Vec::new), &mut origins, &buttons, layers.len());
+    }
+
+    let settings = override_settings(raw_settings, options);
+    let mut problems = validate(&settings);
+
+    if problems.is_empty() {
+      return Ok(settings);
+    }
+
+    for problem in problems.iter_mut() {
+      if !overridden_by_options(problem, options) {
+        locate_problem(problem, layers, &origins);
       }
     }
 
-    let usr_local_path = Path::new("/usr/local/etc/curtains-close/settings.json");
-    if usr_local_path.exists() {
-      return from_file(usr_local_path, &options);
+    problems.sort_by_key(|problem| {
+      let layer_index = layers.iter().position(|layer| problem.source.as_ref() == Some(&layer.source));
+      (layer_index.unwrap_or(usize::MAX), problem.location.unwrap_or((usize::MAX, usize::MAX)))
+    });
+
+    Err(LoadSettingsError::InvalidSettings(problems))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use clap::Parser;
+
+  fn button(id: &str) -> ButtonInfo {
+    serde_json::from_value(serde_json::json!({ "id": id, "icon": "", "text": id, "command": "true" })).unwrap()
+  }
+
+  fn fixed(id: &str, column: u32, row: u32, width: u32, height: u32) -> ButtonInfo {
+    ButtonInfo { column: Some(column), row: Some(row), width: Some(width), height: Some(height), ..button(id) }
+  }
+
+  fn at(column: u32, row: u32, width: u32, height: u32) -> Option<Placement> {
+    Some(Placement { column, row, width, height })
+  }
+
+  #[test]
+  fn flowing_buttons_wrap_after_buttons_per_row() {
+    let buttons = [button("a"), button("b"), button("c"), button("d")];
+    let (placements, errors) = layout_buttons(buttons.iter(), 3);
+
+    assert!(errors.is_empty());
+    assert_eq!(placements, [at(0, 0, 1, 1), at(1, 0, 1, 1), at(2, 0, 1, 1), at(0, 1, 1, 1)]);
+  }
+
+  #[test]
+  fn flowing_buttons_fill_the_cells_around_fixed_tiles() {
+    let buttons = [button("a"), fixed("big", 1, 0, 2, 2), button("b"), button("c"), button("d")];
+    let (placements, errors) = layout_buttons(buttons.iter(), 3);
+
+    assert!(errors.is_empty());
+    assert_eq!(placements, [at(0, 0, 1, 1), at(1, 0, 2, 2), at(0, 1, 1, 1), at(0, 2, 1, 1), at(1, 2, 1, 1)]);
+  }
+
+  #[test]
+  fn wide_flowing_button_skips_to_a_row_with_room() {
+    let buttons = [button("a"), button("b"), ButtonInfo { width: Some(2), ..button("wide") }];
+    let (placements, errors) = layout_buttons(buttons.iter(), 3);
+
+    assert!(errors.is_empty());
+    assert_eq!(placements, [at(0, 0, 1, 1), at(1, 0, 1, 1), at(0, 1, 2, 1)]);
+  }
+
+  #[test]
+  fn overlapping_fixed_tiles_are_reported() {
+    let buttons = [fixed("a", 0, 0, 2, 2), fixed("b", 1, 1, 1, 1), fixed("c", 2, 0, 1, 1)];
+    let (placements, errors) = layout_buttons(buttons.iter(), 3);
+
+    assert_eq!(placements, [at(0, 0, 2, 2), None, at(2, 0, 1, 1)]);
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(&errors[0], (1, LoadSettingsError::OverlappingButtons(id, other)) if id == "b" && other == "a"));
+  }
+
+  #[test]
+  fn incomplete_placements_and_empty_spans_are_reported() {
+    let buttons = [ButtonInfo { row: Some(0), ..button("a") }, ButtonInfo { height: Some(0), ..button("b") }];
+    let (_, errors) = layout_buttons(buttons.iter(), 3);
+
+    assert!(matches!(&errors[0], (0, LoadSettingsError::IncompletePlacement(id)) if id == "a"));
+    assert!(matches!(&errors[1], (1, LoadSettingsError::InvalidSpan(id)) if id == "b"));
+  }
+
+  #[test]
+  fn placements_outside_the_grid_are_reported_without_overflowing() {
+    let buttons = [
+      fixed("past-the-row", 2, 0, 2, 1),
+      fixed("huge-column", u32::MAX, 0, 1, 1),
+      fixed("huge-row", 0, u32::MAX, 1, 1),
+      fixed("past-the-last-row", 0, MAX_GRID_SIZE - 1, 1, 2),
+      ButtonInfo { height: Some(u32::MAX), ..button("huge-height") },
+    ];
+    let (placements, errors) = layout_buttons(buttons.iter(), 3);
+
+    assert!(placements.iter().all(Option::is_none));
+    assert_eq!(errors.iter().map(|(index, _)| *index).collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    assert!(errors.iter().all(|(_, error)| matches!(error, LoadSettingsError::PlacementOutOfRange(_, 3))));
+  }
+
+  #[test]
+  fn place_buttons_returns_the_first_error() {
+    let buttons = [fixed("a", 0, 0, 1, 1), fixed("b", 0, 0, 1, 1), fixed("c", 5, 0, 1, 1)];
+
+    assert!(matches!(place_buttons(buttons.iter(), 3), Err(LoadSettingsError::OverlappingButtons(..))));
+    assert_eq!(place_buttons(buttons[..1].iter(), 3).unwrap(), [Placement { column: 0, row: 0, width: 1, height: 1 }]);
+  }
+
+  const SYSTEM_SETTINGS: &str = r#"{
+  "buttons_per_row": 2,
+  "row_spacing": 1,
+  "buttons": [
+    {"id": "a", "icon": "", "text": "A", "command": "true"},
+    {"id": "b", "icon": "", "text": "b", "command": "true"}
+  ]
+}"#;
+
+  const USER_SETTINGS: &str = r#"{
+  "row_spacing": 5,
+  "buttons": [
+    {"id": "c", "icon": "", "text": "C", "command": "true"},
+    {"id": "b", "icon": "", "text": "B", "command": "true"}
+  ]
+}"#;
+
+  fn layer(source: &str, json: &str) -> Layer {
+    Layer::parse(source.to_string(), json.to_string(), &mut Vec::new()).unwrap()
+  }
+
+  fn layers() -> [Layer; 2] {
+    [layer("system", SYSTEM_SETTINGS), layer("user", USER_SETTINGS)]
+  }
+
+  fn located(path: &str, layers: &[Layer], origins: &ButtonOrigins) -> (Option<String>, Option<(usize, usize)>) {
+    let mut problem = problem(path.to_string(), LoadSettingsError::EmptyShell);
+    locate_problem(&mut problem, layers, origins);
+    (problem.source, problem.location)
+  }
+
+  #[test]
+  fn later_layers_replace_buttons_in_place_and_append_new_ones() {
+    let (merged, origins) = merge_layers(&layers());
+    let buttons = merged.buttons.unwrap();
+
+    assert_eq!(buttons.iter().map(|button| button.id.as_str()).collect::<Vec<_>>(), ["a", "b", "c"]);
+    assert_eq!(buttons[1].text, "B");
+    assert_eq!(origins, [(0, 0), (1, 1), (1, 0)]);
+  }
+
+  #[test]
+  fn later_layers_override_fields_they_set() {
+    let (merged, _) = merge_layers(&layers());
+
+    assert_eq!(merged.buttons_per_row, Some(2));
+    assert_eq!(merged.row_spacing, Some(5));
+  }
+
+  #[test]
+  fn duplicate_ids_within_a_layer_are_kept() {
+    let json = r#"{"buttons": [{"id": "a", "icon": "", "text": "", "command": "true"}, {"id": "a", "icon": "", "text": "", "command": "true"}]}"#;
+    let (merged, origins) = merge_layers(&[layer("user", json)]);
+
+    assert_eq!(merged.buttons.unwrap().len(), 2);
+    assert_eq!(origins, [(0, 0), (0, 1)]);
+  }
+
+  #[test]
+  fn button_problems_are_located_in_the_layer_the_button_came_from() {
+    let layers = layers();
+    let (_, origins) = merge_layers(&layers);
+
+    assert_eq!(located("buttons[0].id", &layers, &origins), (Some("system".to_string()), Some((5, 12))));
+    assert_eq!(located("buttons[1].text", &layers, &origins), (Some("user".to_string()), Some((5, 37))));
+    assert_eq!(located("buttons[2]", &layers, &origins), (Some("user".to_string()), Some((4, 5))));
+    assert_eq!(located("buttons[3]", &layers, &origins), (None, None));
+  }
+
+  #[test]
+  fn field_problems_are_located_in_the_last_layer_setting_the_field() {
+    let layers = layers();
+    let (_, origins) = merge_layers(&layers);
+
+    assert_eq!(located("buttons_per_row", &layers, &origins), (Some("system".to_string()), Some((2, 22))));
+    assert_eq!(located("row_spacing", &layers, &origins), (Some("user".to_string()), Some((2, 18))));
+    assert_eq!(located("default_action", &layers, &origins), (None, None));
+  }
+
+  fn load(json: &str, args: &[&str]) -> Result<Settings, LoadSettingsError> {
+    let options = Options::parse_from(["curtains-close"].iter().chain(args));
+    Settings::from_layers(&[layer("settings.json", json)], &options)
+  }
+
+  /// Path, location and message of a problem.
+  type ReportedProblem = (String, Option<(usize, usize)>, String);
+
+  /// Every problem in the order they are reported.
+  fn problems(json: &str, args: &[&str]) -> Vec<ReportedProblem> {
+    match load(json, args) {
+      Err(LoadSettingsError::InvalidSettings(problems)) => problems
+        .into_iter()
+        .map(|problem| (problem.path, problem.location, problem.error.to_string()))
+        .collect(),
+      Err(e) => panic!("unexpected error: {}", e),
+      Ok(_) => Vec::new(),
     }
+  }
+
+  fn problem_at(path: &str, location: Option<(usize, usize)>, message: &str) -> ReportedProblem {
+    (path.to_string(), location, message.to_string())
+  }
+
+  #[test]
+  fn valid_settings_load() {
+    let settings = load(USER_SETTINGS, &[]).unwrap();
+
+    assert_eq!(settings.buttons.len(), 2);
+    assert_eq!(settings.row_spacing, 5);
+  }
+
+  #[test]
+  fn duplicate_ids_are_reported_across_pages() {
+    let json = r#"{"buttons": [
+  {"id": "power", "icon": "", "text": "", "children": [
+    {"id": "lock", "icon": "", "text": "", "command": "true"}
+  ]},
+  {"id": "lock", "icon": "", "text": "", "command": "true"}
+]}"#;
+
+    assert_eq!(problems(json, &[]), [
+      problem_at("buttons[1].id", Some((5, 10)), "Button id 'lock' is used by more than one button"),
+    ]);
+  }
+
+  #[test]
+  fn duplicate_keybinds_are_reported_within_a_page() {
+    let json = r#"{"buttons": [
+  {"id": "a", "icon": "", "text": "", "command": "true", "keybind": "a"},
+  {"id": "b", "icon": "", "text": "", "command": "true", "keybind": ["b", "a"]},
+  {"id": "c", "icon": "", "text": "", "children": [
+    {"id": "d", "icon": "", "text": "", "command": "true", "keybind": "a"}
+  ]}
+]}"#;
 
-    let etc_path = Path::new("/etc/curtains-close/settings.json");
-    if etc_path.exists() {
-      return from_file(etc_path, &options);
-    }    
+    assert_eq!(problems(json, &[]), [
+      problem_at("buttons[1].keybind[1]", Some((3, 75)), "Keybind 'a' of button 'b' is already used by button 'a'"),
+    ]);
+  }
+
+  #[test]
+  fn keybinds_conflicting_with_dismiss_keys_are_reported() {
+    let json = r#"{
+  "dismiss_keys": ["q", "Escape"],
+  "buttons": [
+    {"id": "a", "icon": "", "text": "", "command": "true", "keybind": "Escape"}
+  ]
+}"#;
+
+    assert_eq!(problems(json, &[]), [
+      problem_at("buttons[0].keybind", Some((4, 71)), "Dismiss key 'Escape' is also the keybind of button 'a'"),
+    ]);
+    assert!(problems(json, &["--no-dismiss"]).is_empty());
+  }
+
+  #[test]
+  fn invalid_accelerators_are_reported() {
+    let json = r#"{
+  "dismiss_keys": ["NotAKey"],
+  "buttons": [
+    {"id": "a", "icon": "", "text": "", "command": "true", "keybind": ["a", "NotAKey"]}
+  ]
+}"#;
+
+    assert_eq!(problems(json, &[]), [
+      problem_at("dismiss_keys[0]", Some((2, 20)), "Dismiss key 'NotAKey' is not a valid accelerator"),
+      problem_at("buttons[0].keybind[1]", Some((4, 77)), "Keybind 'NotAKey' of button 'a' is not a valid accelerator"),
+    ]);
+  }
+
+  #[test]
+  fn empty_commands_and_missing_commands_are_reported() {
+    let json = r#"{"buttons": [
+  {"id": "a", "icon": "", "text": "", "command": " "},
+  {"id": "b", "icon": "", "text": "", "command": []},
+  {"id": "c", "icon": "", "text": ""},
+  {"id": "d", "icon": "", "text": "", "command": "true", "action": "lock"}
+]}"#;
+
+    assert_eq!(problems(json, &[]), [
+      problem_at("buttons[0].command", Some((2, 50)), "Button 'a' has an empty command"),
+      problem_at("buttons[1].command", Some((3, 50)), "Button 'b' has an empty command"),
+      problem_at("buttons[2]", Some((4, 3)), "Button 'c' must have exactly one of command, action or children"),
+      problem_at("buttons[3]", Some((5, 3)), "Button 'd' must have exactly one of command, action or children"),
+    ]);
+  }
+
+  #[test]
+  fn problems_with_command_line_values_have_no_location() {
+    let json = r#"{
+  "default_action": "a",
+  "buttons": [{"id": "a", "icon": "", "text": "", "command": "true"}]
+}"#;
+
+    assert!(problems(json, &[]).is_empty());
+    assert_eq!(problems(json, &["--default-action", "missing"]), [
+      problem_at("default_action", None, "Default action 'missing' does not match the id of any button"),
+    ]);
+  }
+
+  #[test]
+  fn command_line_buttons_are_merged_by_id() {
+    let buttons = r#"[{"id": "c", "icon": "", "text": "Cli", "command": "true"}, {"id": "e", "icon": "", "text": "E", "command": "true"}]"#;
+    let settings = load(USER_SETTINGS, &["--buttons", buttons]).unwrap();
 
-    Err(LoadSettingsError::ContentOrPathNotFound)
+    assert_eq!(settings.buttons.iter().map(|button| button.id.as_str()).collect::<Vec<_>>(), ["c", "b", "e"]);
+    assert_eq!(settings.buttons[0].text, "Cli");
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn command_line_button_problems_have_no_location() {
+    let buttons = r#"[{"id": "e", "icon": "", "text": "E", "command": ""}]"#;
+    let json = r#"{"buttons": [{"id": "a", "icon": "", "text": "", "command": ""}]}"#;
+
+    assert_eq!(problems(json, &["--buttons", buttons]), [
+      problem_at("buttons[0].command", Some((1, 61)), "Button 'a' has an empty command"),
+      problem_at("buttons[1].command", None, "Button 'e' has an empty command"),
+    ]);
+  }
+}